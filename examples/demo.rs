@@ -0,0 +1,505 @@
+//! Builds every model and writes each export format to target/exports, then regenerates the
+//! designs from the records embedded in the files: `cargo run --release --example demo`
+
+use compression_waveguide::baffle::{add_baffle, Baffle, EdgeProfile};
+use compression_waveguide::element_mesh::{ElementMeshOptions, ElementShape, ElementSize};
+use compression_waveguide::enclosure::{generate_enclosure, Enclosure};
+use compression_waveguide::export::{profile_sketch, read_stl, write_3mf, write_cadquery, write_openscad, ScriptOptions, ThreeMfObject, write_glb, write_obj, write_ply, PlyAttribute, PlyFormat, write_dxf, write_iges, write_step, DxfCurve, DxfEntity, solids_to_mesh, write_mesh_stl, write_stl, write_stl_per_tag, StlFormat, StlSolid};
+use compression_waveguide::geometry_types::{profile_curvatures, texture_coordinates, wall_angles, CartesianPoint, ProfilePoint};
+use compression_waveguide::mesh::{triangulate_profiles, Mesh, SurfaceTag, Symmetry};
+use compression_waveguide::record::{read_record, AxialResolution, DesignModel, DesignOutput, DesignRecord};
+use compression_waveguide::throat::{add_throat_cap, add_throat_extension, generate_flange, generate_throat_adapter, Flange, ThroatAdapter, ThroatCap, ThroatExtension};
+use compression_waveguide::trim::{add_baffle_ring, trim_at_plane, Plane};
+use compression_waveguide::models::{self, AzimuthalSampling, AzimuthalValue, EllipsoidalOSWG, Waveguide, AxisymOSWG, RectangularOSWG, RectangularMorphOSWG, TabulatedWG, ClassicHornWG, HornProfile, LeCleachWG, Termination, TerminationEnd};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+/// Writes profile points to CSV file (for debugging/visualization), the design record in a
+/// leading comment
+fn export_coordinates_to_csv(points: &[ProfilePoint], record: &DesignRecord, filename: &str) -> std::io::Result<()> {
+    #[derive(Serialize)]
+    struct CsvPoint {
+        z: f64,
+        r: f64,
+        theta: f64,
+        x: f64,
+        y: f64,
+    }
+
+    let mut file = std::fs::File::create(filename)?;
+    writeln!(file, "# design {}", record.to_json())?;
+    let mut writer = csv::Writer::from_writer(file);
+
+    for point in points {
+        let cartesian = CartesianPoint::from_cylindrical(point.r, point.theta, point.z);
+        writer.serialize(CsvPoint {
+            z: point.z,
+            r: point.r,
+            theta: point.theta,
+            x: cartesian.x,
+            y: cartesian.y,
+        })?;
+    }
+
+    writer.flush()
+}
+
+
+/// Exports waveguide mesh to a binary STL file, the solid named after the file and the design
+/// record in its JSON sidecar
+fn export_stl(mesh: &[[CartesianPoint; 3]], record: &DesignRecord, filename: &str) -> std::io::Result<()> {
+    let name = Path::new(filename).file_stem().and_then(|stem| stem.to_str()).unwrap_or("waveguide");
+    write_stl(&[StlSolid { name: name.to_string(), triangles: mesh.to_vec() }], StlFormat::Binary, Some(record), filename)
+}
+
+/// Record of an export built from the design of `record` by the further step `name`
+fn derived(record: &DesignRecord, name: &str) -> DesignRecord {
+    DesignRecord { output: DesignOutput::Derived(name.to_string()), ..record.clone() }
+}
+
+fn main() -> std::io::Result<()> {
+    let waveguide_length = 200.0; // mm
+    let azimuthal_steps = 36; // 10° resolution
+    let axial_steps = 50;
+
+    let ellipsoidal = EllipsoidalOSWG {
+        k: 1.0,
+        r_init: 25.4,
+        alpha_init: 1.0f64.to_radians(),
+        tilt: 0.0,
+        s: 0.7.into(),
+        q: 0.997.into(),
+        n: 6.0.into(),
+        termination: None,
+        alpha_h: 45.0f64.to_radians(),
+        alpha_v: 30.0f64.to_radians(),
+    };
+    let ellipsoidal_record = DesignRecord::new(
+        DesignModel::EllipsoidalOSWG(ellipsoidal.clone()),
+        waveguide_length,
+        azimuthal_steps,
+        axial_steps,
+    );
+    // Generate and export a sample profile for inspection
+    let test_profile = ellipsoidal.generate_profile(waveguide_length, 0.0, axial_steps)?;
+    export_coordinates_to_csv(&test_profile, &ellipsoidal_record, "target/exports/waveguide_profile.csv")?;
+
+    // Horizontal and vertical profiles as sketch splines, and as 3D polylines in place
+    let sections: Vec<(String, Vec<ProfilePoint>)> = [0.0f64, 90.0]
+        .iter()
+        .map(|angle| Ok((format!("theta_{}", angle), ellipsoidal.generate_profile(waveguide_length, angle.to_radians(), axial_steps)?)))
+        .collect::<std::io::Result<_>>()?;
+    let sketches: Vec<DxfCurve> = sections
+        .iter()
+        .map(|(layer, profile)| DxfCurve { layer: layer.clone(), points: profile_sketch(profile) })
+        .collect();
+    write_dxf(&sketches, DxfEntity::Spline, Some(&ellipsoidal_record), "target/exports/waveguide_profiles.dxf")?;
+    let placed: Vec<DxfCurve> = sections
+        .iter()
+        .map(|(layer, profile)| DxfCurve {
+            layer: layer.clone(),
+            points: profile.iter().map(|point| ellipsoidal.place_point(point, waveguide_length)).collect(),
+        })
+        .collect();
+    write_dxf(&placed, DxfEntity::Polyline, Some(&ellipsoidal_record), "target/exports/waveguide_profiles_3d.dxf")?;
+
+    // Read the exported profile back as an axisymmetric tabulated waveguide
+    let tabulated = TabulatedWG::from_csv("target/exports/waveguide_profile.csv")?;
+    let tabulated_record = DesignRecord::new(
+        DesignModel::TabulatedWG(tabulated.clone()),
+        tabulated.length(),
+        azimuthal_steps,
+        axial_steps,
+    );
+    let tabulated_triangles = tabulated.generate_mesh(tabulated.length(), azimuthal_steps, axial_steps)?;
+    export_stl(&tabulated_triangles, &tabulated_record, "target/exports/tabulated.stl")?;
+
+    // Generate full 3D mesh and export
+    let triangles = ellipsoidal.generate_mesh(waveguide_length, azimuthal_steps, axial_steps)?;
+    export_stl(&triangles, &ellipsoidal_record, "target/exports/ellipsoidal.stl")?;
+
+    // Cut before the OS-SE roll-off and mount the mouth in a flat baffle ring for simulation
+    let mouth_plane = Plane::at_z(180.0);
+    let mut baffled = trim_at_plane(&Mesh::from_triangles(&triangles, SurfaceTag::Wall), &mouth_plane);
+    add_baffle_ring(&mut baffled, &mouth_plane, 300.0)?;
+    export_stl(&baffled.to_triangles(), &derived(&ellipsoidal_record, "baffle ring"), "target/exports/ellipsoidal_baffled.stl")?;
+
+    // Rectangular baffle with rounded corners and a front roundover around the trimmed mouth
+    let mut mounted = trim_at_plane(&Mesh::from_triangles(&triangles, SurfaceTag::Wall), &mouth_plane);
+    add_baffle(&mut mounted, &mouth_plane, &Baffle {
+        width: 400.0,
+        height: 480.0,
+        corner_radius: 40.0,
+        edge: EdgeProfile::Roundover(20.0),
+        depth: 60.0,
+    })?;
+    export_stl(&mounted.to_triangles(), &derived(&ellipsoidal_record, "rectangular baffle"), "target/exports/ellipsoidal_rect_baffle.stl")?;
+
+    // Same trimmed waveguide sunk into a closed box with chamfered front edges
+    let boxed = generate_enclosure(
+        &trim_at_plane(&Mesh::from_triangles(&triangles, SurfaceTag::Wall), &mouth_plane),
+        &mouth_plane,
+        &Plane::at_z(0.0),
+        &Enclosure {
+            width: 400.0,
+            height: 480.0,
+            depth: 250.0,
+            corner_radius: 0.0,
+            edge: EdgeProfile::Chamfer(15.0),
+            source: ellipsoidal.throat_cap(),
+        },
+    )?;
+    let enclosure_record = derived(&ellipsoidal_record, "enclosure");
+    export_stl(&boxed.to_triangles(), &enclosure_record, "target/exports/ellipsoidal_enclosure.stl")?;
+
+    // Viewer formats: the wall with θ/z texture coordinates and per-vertex wall angle and
+    // curvature, and the tagged enclosure for the web
+    let wall_profiles = ellipsoidal.generate_profiles(waveguide_length, azimuthal_steps, axial_steps)?;
+    let wall_grid: Vec<Vec<CartesianPoint>> = wall_profiles
+        .iter()
+        .map(|profile| profile.iter().map(|point| ellipsoidal.place_point(point, waveguide_length)).collect())
+        .collect();
+    let wall = Mesh::from_grid(&wall_grid, true, SurfaceTag::Wall);
+    let wall_uvs: Vec<[f64; 2]> =
+        wall_profiles.iter().flat_map(|profile| texture_coordinates(profile, waveguide_length)).collect();
+    write_obj(&wall, Some(&wall_uvs), Some(&ellipsoidal_record), "target/exports/ellipsoidal.obj")?;
+    let wall_attributes = [
+        PlyAttribute {
+            name: "wall_angle".to_string(),
+            values: wall_profiles.iter().flat_map(|profile| wall_angles(profile)).map(f64::to_degrees).collect(),
+        },
+        PlyAttribute {
+            name: "curvature".to_string(),
+            values: wall_profiles.iter().flat_map(|profile| profile_curvatures(profile)).collect(),
+        },
+    ];
+    write_ply(&wall, &wall_attributes, PlyFormat::Binary, Some(&ellipsoidal_record), "target/exports/ellipsoidal.ply")?;
+    write_obj(&boxed, None, Some(&enclosure_record), "target/exports/ellipsoidal_enclosure.obj")?;
+    write_glb(&boxed, "ellipsoidal_enclosure", Some(&enclosure_record), "target/exports/ellipsoidal_enclosure.glb")?;
+
+    // Tagged surfaces as named solids of one ASCII file, and as one file per surface
+    write_mesh_stl(&boxed, StlFormat::Ascii, Some(&enclosure_record), "target/exports/ellipsoidal_enclosure_tagged.stl")?;
+    write_stl_per_tag(&boxed, StlFormat::Binary, Some(&enclosure_record), "target/exports/ellipsoidal_enclosure.stl")?;
+    let reimported = solids_to_mesh(&read_stl("target/exports/ellipsoidal_enclosure_tagged.stl")?);
+    println!(
+        "Re-imported enclosure: {} vertices, {} triangles, {} boundary loops",
+        reimported.vertices.len(),
+        reimported.triangles.len(),
+        reimported.boundary_loops().len()
+    );
+
+    // Same waveguide with its axis steered 10° downward, mouth kept planar
+    let tilted = EllipsoidalOSWG {
+        tilt: 10.0f64.to_radians(),
+        ..ellipsoidal
+    };
+    let tilted_record = DesignRecord::new(
+        DesignModel::EllipsoidalOSWG(tilted.clone()),
+        waveguide_length,
+        azimuthal_steps,
+        axial_steps,
+    );
+    let tilted_triangles = tilted.generate_mesh(waveguide_length, azimuthal_steps, axial_steps)?;
+    export_stl(&tilted_triangles, &tilted_record, "target/exports/ellipsoidal_tilted.stl")?;
+
+    // Editable scripts rebuilding a walled horn with a throat flange, the wall shared by every solid
+    let wall_thickness = 6.0; // mm
+    let script_options = ScriptOptions {
+        length: waveguide_length,
+        tilt: tilted.tilt,
+        thickness: wall_thickness,
+        flange: Some(Flange { outer_radius: 60.0, thickness: 8.0 }),
+    };
+    let tilted_profiles = tilted.generate_profiles(waveguide_length, azimuthal_steps, axial_steps)?;
+    write_openscad(&tilted_profiles, &script_options, "ellipsoidal_tilted", Some(&tilted_record), "target/exports/ellipsoidal_tilted.scad")?;
+    write_cadquery(&tilted_profiles, &script_options, "ellipsoidal_tilted", Some(&tilted_record), "target/exports/ellipsoidal_tilted.py")?;
+    let tilted_solid = tilted.generate_solid(waveguide_length, azimuthal_steps, axial_steps, wall_thickness)?;
+    let tilted_solid_record = DesignRecord { output: DesignOutput::Solid { thickness: wall_thickness }, ..tilted_record.clone() };
+    export_stl(&tilted_solid, &tilted_solid_record, "target/exports/ellipsoidal_tilted_solid.stl")?;
+
+    let axisym = AxisymOSWG {
+        k: 1.0,
+        r_init: 25.4,
+        alpha_init: 1.0f64.to_radians(),
+        tilt: 0.0,
+        s: 0.7,
+        q: 0.997,
+        n: 6.0,
+        termination: None,
+        alpha: 45.0f64.to_radians(),
+    };
+    let axisym_record =
+        DesignRecord::new(DesignModel::AxisymOSWG(axisym.clone()), waveguide_length, azimuthal_steps, axial_steps);
+    let axi_triangles = axisym.generate_mesh(waveguide_length, azimuthal_steps, axial_steps)?;
+    export_stl(&axi_triangles, &axisym_record, "target/exports/axisymmetric.stl")?;
+
+    // Conical section down to a 1" driver exit, closed by a spherical wavefront cap
+    let mut axi_driver = Mesh::from_triangles(&axi_triangles, SurfaceTag::Wall);
+    let exit = add_throat_extension(&mut axi_driver, &Plane::at_z(0.0), &ThroatExtension::Conical {
+        length: 15.0,
+        angle: 5.0f64.to_radians(),
+    })?;
+    add_throat_cap(&mut axi_driver, &exit, &ThroatCap::Spherical { radius: 60.0 })?;
+    export_stl(&axi_driver.to_triangles(), &derived(&axisym_record, "driver exit"), "target/exports/axisymmetric_driver_exit.stl")?;
+
+    // Throat driven by the spherical OS wavefront, and by a flat disc for comparison
+    let axi_source = axisym.generate_source_mesh(waveguide_length, azimuthal_steps, axial_steps, &axisym.throat_cap())?;
+    export_stl(&axi_source.to_triangles(), &derived(&axisym_record, "wavefront source"), "target/exports/axisymmetric_source.stl")?;
+    let axi_flat = axisym.generate_source_mesh(waveguide_length, azimuthal_steps, axial_steps, &ThroatCap::Flat)?;
+    export_stl(&axi_flat.to_triangles(), &derived(&axisym_record, "flat source"), "target/exports/axisymmetric_source_flat.stl")?;
+
+    // NURBS wall for CAD, fitted within 0.05 mm
+    let (axi_wall, axi_deviation) = axisym.fit_wall_surface(waveguide_length, 0.05)?;
+    if axi_deviation > 0.05 {
+        println!("warning: axisymmetric NURBS wall deviates by {:.3e} mm", axi_deviation);
+    }
+    let axi_surface = [axi_wall];
+    write_step(&axi_surface, false, "axisymmetric", Some(&axisym_record), "target/exports/axisymmetric.step")?;
+    write_iges(&axi_surface, "axisymmetric", Some(&axisym_record), "target/exports/axisymmetric.igs")?;
+
+    // Pure OS profile rolled back with a tangent circular arc instead of the OS-SE term
+    let axisym_arc = AxisymOSWG {
+        termination: Some(Termination::CircularArc { radius: 40.0, angle: 120.0f64.to_radians() }),
+        ..axisym
+    };
+    let axisym_arc_record =
+        DesignRecord::new(DesignModel::AxisymOSWG(axisym_arc.clone()), waveguide_length, azimuthal_steps, axial_steps);
+    let arc_profile = axisym_arc.generate_profile(waveguide_length, 0.0, axial_steps)?;
+    export_coordinates_to_csv(&arc_profile, &axisym_arc_record, "target/exports/arc_waveguide_profile.csv")?;
+    let axi_arc_triangles = axisym_arc.generate_mesh(waveguide_length, azimuthal_steps, axial_steps)?;
+    export_stl(&axi_arc_triangles, &axisym_arc_record, "target/exports/axisymmetric_arc.stl")?;
+
+    let rectangular =  RectangularOSWG {
+        k: 1.0,
+        r_init: 25.4,
+        alpha_init: 1.0f64.to_radians(),
+        tilt: 0.0,
+        // shorter roll-back on the narrow vertical mouth
+        s: AzimuthalValue::Rectangular { h: 0.7, v: 0.5 },
+        q: 0.997.into(),
+        n: 6.0.into(),
+        termination: None,
+        alpha_h: 45.0f64.to_radians(),
+        alpha_v: 30.0f64.to_radians(),
+    };
+    let rectangular_record = DesignRecord::new(
+        DesignModel::RectangularOSWG(rectangular.clone()),
+        waveguide_length,
+        azimuthal_steps,
+        axial_steps,
+    );
+    // Mesh the repaired grid, reporting any degenerate triangle the repair leaves in it
+    let (rect_grid, remaining) = rectangular.generate_grid(waveguide_length, azimuthal_steps, axial_steps, Symmetry::Full, true)?;
+    for defect in remaining {
+        println!("warning: rectangular_alpha: {} left after repair", defect);
+    }
+    let rect_triangles = triangulate_profiles(&rect_grid);
+    export_stl(&rect_triangles, &rectangular_record, "target/exports/rectangular_alpha.stl")?;
+
+    // Same angle count, spread evenly along the mouth contour instead of evenly in θ
+    let rect_arc_triangles = rectangular.generate_mesh(
+        waveguide_length,
+        AzimuthalSampling::MouthArcLength(azimuthal_steps),
+        axial_steps,
+    )?;
+    let rect_arc_record = DesignRecord {
+        azimuth: AzimuthalSampling::MouthArcLength(azimuthal_steps),
+        ..rectangular_record.clone()
+    };
+    export_stl(&rect_arc_triangles, &rect_arc_record, "target/exports/rectangular_alpha_arc_sampled.stl")?;
+
+    // BEM mesh resolving 4 kHz, elements graded from a third of the size at the throat
+    let (rect_bem, rect_bem_defects) = rectangular.generate_element_mesh(waveguide_length, &ElementMeshOptions {
+        size: ElementSize::MaxFrequency(4000.0),
+        throat_ratio: 0.33,
+        shape: ElementShape::Triangle,
+        symmetry: Symmetry::Full,
+    })?;
+    for defect in rect_bem_defects {
+        println!("warning: rectangular_alpha_bem: {}", defect);
+    }
+    export_stl(&rect_bem.to_mesh().to_triangles(), &derived(&rectangular_record, "element mesh"), "target/exports/rectangular_alpha_bem.stl")?;
+
+    // Quarter model for a faster BEM run, mirrored in the x = 0 and y = 0 planes by the solver
+    let rect_quarter = rectangular.generate_sector_mesh(waveguide_length, azimuthal_steps, axial_steps, Symmetry::Quarter)?;
+    export_stl(&rect_quarter.to_triangles(), &derived(&rectangular_record, "quarter sector"), "target/exports/rectangular_alpha_quarter.stl")?;
+    
+    let rectangular_morph =  RectangularMorphOSWG {
+        k: 1.0,
+        r_init: 25.4,
+        alpha_init: 1.0f64.to_radians(),
+        tilt: 0.0,
+        s: 0.7,
+        q: 0.997,
+        n: 6.0,
+        termination: None,
+        alpha_h: 45.0f64.to_radians(),
+        alpha_v: 30.0f64.to_radians(),
+    };
+    let rect_morph_record = DesignRecord::new(
+        DesignModel::RectangularMorphOSWG(rectangular_morph.clone()),
+        waveguide_length,
+        azimuthal_steps,
+        axial_steps,
+    );
+    let rect_morph_triangles = rectangular_morph.generate_mesh(waveguide_length, azimuthal_steps, axial_steps)?;
+    export_stl(&rect_morph_triangles, &rect_morph_record, "target/exports/rectangular_morph.stl")?;
+
+    // The clothoid terminated models are sampled every 4 mm along the OS part
+    let clothoid_steps = AxialResolution::from(4.0).steps(waveguide_length);
+    let axisym_clothoid = models::AxisymOSCWG {
+        k: 1.0,
+        r_init: 25.4,
+        alpha_init: 1.0f64.to_radians(),
+        tilt: 0.0,
+        termination: Termination::Clothoid(TerminationEnd::Length { length: 200.0, end_radius: 60.0 }), // mm
+        alpha: 45.0f64.to_radians(),
+    };
+    let axisym_clothoid_record =
+        DesignRecord::new(DesignModel::AxisymOSCWG(axisym_clothoid.clone()), waveguide_length, 2*azimuthal_steps, 4.0);
+    let test_profile = axisym_clothoid.generate_profile(waveguide_length, 0.0, clothoid_steps)?;
+    export_coordinates_to_csv(&test_profile, &axisym_clothoid_record, "target/exports/clothoid_waveguide_profile.csv")?;
+    let axi_clothoid_triangles = axisym_clothoid.generate_mesh(waveguide_length, 2*azimuthal_steps, clothoid_steps)?;
+    export_stl(&axi_clothoid_triangles, &axisym_clothoid_record, "target/exports/axi_clothoid_triangles.stl")?;
+
+    // Free-standing horn: the mouth rolls back into a toroidal lip closing onto the wall
+    let axisym_lip = models::AxisymOSCWG {
+        termination: Termination::Lip { radius: 15.0, thickness: wall_thickness }, // mm
+        ..axisym_clothoid
+    };
+    let lip_record = DesignRecord {
+        output: DesignOutput::Solid { thickness: wall_thickness },
+        ..DesignRecord::new(DesignModel::AxisymOSCWG(axisym_lip.clone()), waveguide_length, 2*azimuthal_steps, 4.0)
+    };
+    let lip_solid = axisym_lip.generate_solid(waveguide_length, 2*azimuthal_steps, clothoid_steps, wall_thickness)?;
+    export_stl(&lip_solid, &lip_record, "target/exports/axi_lip_solid.stl")?;
+    let (lip_faces, lip_deviation) = axisym_lip.fit_solid_surfaces(waveguide_length, wall_thickness, 0.05)?;
+    if lip_deviation > 0.05 {
+        println!("warning: axi_lip_solid NURBS faces deviate by {:.3e} mm", lip_deviation);
+    }
+    write_step(&lip_faces, true, "axi_lip_solid", Some(&lip_record), "target/exports/axi_lip_solid.step")?;
+    write_iges(&lip_faces, "axi_lip_solid", Some(&lip_record), "target/exports/axi_lip_solid.igs")?;
+
+    // Printable set for a slicer: the horn, a throat adapter down to a 1.4" driver exit and
+    // its mounting flange, as separate objects of one millimetre build
+    let lip_profiles = axisym_lip.generate_profiles(waveguide_length, 2*azimuthal_steps, clothoid_steps)?;
+    let lip_place = |point: &ProfilePoint| axisym_lip.place_point(point, waveguide_length);
+    let adapter = ThroatAdapter { length: 30.0, exit_radius: 17.8, thickness: wall_thickness };
+    let flange = Flange { outer_radius: 60.0, thickness: 8.0 };
+    let printable = [
+        ThreeMfObject {
+            name: "horn".to_string(),
+            mesh: Mesh::from_triangles(&lip_solid, SurfaceTag::Wall),
+            metadata: vec![
+                ("design".to_string(), lip_record.to_json()),
+                ("wall_thickness".to_string(), wall_thickness.to_string()),
+            ],
+        },
+        ThreeMfObject {
+            name: "throat_adapter".to_string(),
+            mesh: Mesh::from_triangles(&generate_throat_adapter(&lip_profiles, &adapter, lip_place), SurfaceTag::Wall),
+            metadata: vec![("exit_radius".to_string(), adapter.exit_radius.to_string())],
+        },
+        ThreeMfObject {
+            name: "flange".to_string(),
+            mesh: Mesh::from_triangles(&generate_flange(&lip_profiles, &adapter, &flange, lip_place), SurfaceTag::Wall),
+            metadata: Vec::new(),
+        },
+    ];
+    write_3mf(&printable, "axi_lip", "target/exports/axi_lip.3mf")?;
+
+    let rect_clothoid = models::RectOSCWG {
+        k: 1.0,
+        r_init: 25.4,
+        alpha_init: 1.0f64.to_radians(),
+        tilt: 0.0,
+        // roll back until the wall is parallel to the mouth plane
+        termination: Termination::Clothoid(TerminationEnd::WallAngle { angle: 90.0f64.to_radians(), end_radius: 50.0 }), // mm
+        alpha_h: 45.0f64.to_radians(),
+        alpha_v: 30.0f64.to_radians(),
+    };
+
+    let rect_clothoid_record =
+        DesignRecord::new(DesignModel::RectOSCWG(rect_clothoid.clone()), waveguide_length, azimuthal_steps, 4.0);
+    let rect_clothoid_triangles = rect_clothoid.generate_mesh(waveguide_length, azimuthal_steps, clothoid_steps)?;
+    export_stl(&rect_clothoid_triangles, &rect_clothoid_record, "target/exports/rect_clothoid.stl")?;
+
+    // Flat mouth for baffle mounting: every angle ends in the plane z = 240 mm, parallel to it
+    let rect_clothoid_flat = models::RectOSCWG {
+        termination: Termination::Clothoid(TerminationEnd::MouthPlane { z: 240.0, angle: 90.0f64.to_radians() }),
+        ..rect_clothoid
+    };
+    let rect_clothoid_flat_record =
+        DesignRecord::new(DesignModel::RectOSCWG(rect_clothoid_flat.clone()), waveguide_length, azimuthal_steps, 4.0);
+    let rect_clothoid_flat_triangles = rect_clothoid_flat.generate_mesh(waveguide_length, azimuthal_steps, clothoid_steps)?;
+    export_stl(&rect_clothoid_flat_triangles, &rect_clothoid_flat_record, "target/exports/rect_clothoid_flat.stl")?;
+
+    let le_cleach = ClassicHornWG::new(
+        12.7,
+        500.0, // Hz
+        150.0, // mm
+        0.0,
+        HornProfile::LeCleach { t: 0.7 },
+    )?;
+    let le_cleach_record = DesignRecord::new(
+        DesignModel::ClassicHornWG(le_cleach.clone()),
+        le_cleach.length(),
+        azimuthal_steps,
+        axial_steps,
+    );
+    let le_cleach_triangles = le_cleach.generate_mesh(le_cleach.length(), azimuthal_steps, axial_steps)?;
+    export_stl(&le_cleach_triangles, &le_cleach_record, "target/exports/le_cleach.stl")?;
+
+    let rect_le_cleach = LeCleachWG::new(
+        12.7,
+        500.0, // Hz
+        0.7,
+        AzimuthalValue::Rectangular { h: 160.0, v: 90.0 }, // mm
+        0.0,
+    )?;
+    let rect_le_cleach_record = DesignRecord::new(
+        DesignModel::LeCleachWG(rect_le_cleach.clone()),
+        rect_le_cleach.length(),
+        azimuthal_steps,
+        axial_steps,
+    );
+    let rect_le_cleach_triangles =
+        rect_le_cleach.generate_mesh(rect_le_cleach.length(), azimuthal_steps, axial_steps)?;
+    export_stl(&rect_le_cleach_triangles, &rect_le_cleach_record, "target/exports/rect_le_cleach.stl")?;
+
+    // Regenerate designs from the records embedded in their exports, or from the sidecar of
+    // binary STL files
+    for file in [
+        "target/exports/waveguide_profile.csv",
+        "target/exports/tabulated.stl",
+        "target/exports/ellipsoidal_enclosure_tagged.stl",
+        "target/exports/ellipsoidal.ply",
+        "target/exports/ellipsoidal_enclosure.glb",
+        "target/exports/ellipsoidal_tilted.scad",
+        "target/exports/axisymmetric.igs",
+        "target/exports/axi_lip_solid.step",
+        "target/exports/axi_lip.3mf",
+        "target/exports/rect_le_cleach.stl",
+    ] {
+        let record = read_record(file)?;
+        if record.version != env!("CARGO_PKG_VERSION") {
+            println!("warning: {} was written by {} {}", file, record.generator, record.version);
+        }
+        match record.generate_mesh() {
+            Ok(mesh) => println!("{}: {}, {} triangles regenerated", file, record.summary(), mesh.len()),
+            Err(error) => println!("{}: {}, {}", file, record.summary(), error),
+        }
+    }
+    let regenerated = read_record("target/exports/ellipsoidal.stl")?.generate_mesh()?;
+    let deviation = regenerated
+        .iter()
+        .flatten()
+        .zip(triangles.iter().flatten())
+        .map(|(a, b)| (*a - *b).norm())
+        .fold(0.0, f64::max);
+    println!("Regenerated ellipsoidal.stl: {} triangles, {:e} mm from the export", regenerated.len(), deviation);
+
+    println!("Successfully exported waveguide data");
+    Ok(())
+}
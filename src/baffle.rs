@@ -0,0 +1,302 @@
+use crate::geometry_types::CartesianPoint;
+use crate::mesh::{Mesh, SurfaceTag};
+use crate::trim::{mouth_centre, require_mouth_loop, Plane};
+use std::f64::consts::PI;
+use std::io;
+
+/// Treatment of the front edge between the baffle face and the sides
+#[derive(Debug, Clone, Copy)]
+pub enum EdgeProfile {
+    Sharp,
+    Roundover(f64), // radius
+    Chamfer(f64),   // 45° cut, set back by this distance on both faces
+}
+
+impl EdgeProfile {
+    /// Distance the edge eats into the front face and into the sides
+    fn size(&self) -> f64 {
+        match *self {
+            EdgeProfile::Sharp => 0.0,
+            EdgeProfile::Roundover(radius) => radius,
+            EdgeProfile::Chamfer(size) => size,
+        }
+    }
+
+    /// (outward, back) offsets along the edge, from the front face to the sides
+    fn sweep(&self, segment: f64) -> Vec<(f64, f64)> {
+        match *self {
+            EdgeProfile::Sharp => vec![(0.0, 0.0)],
+            EdgeProfile::Roundover(radius) => {
+                let steps = ((radius * PI / 2.0) / segment).ceil().max(2.0) as usize;
+                (0..=steps)
+                    .map(|k| {
+                        let angle = k as f64 / steps as f64 * PI / 2.0;
+                        (radius * angle.sin(), radius * (1.0 - angle.cos()))
+                    })
+                    .collect()
+            }
+            EdgeProfile::Chamfer(size) => vec![(0.0, 0.0), (size, size)],
+        }
+    }
+}
+
+/// Rectangular baffle centred on the mouth, built around the mouth edge in the mouth plane
+#[derive(Debug, Clone, Copy)]
+pub struct Baffle {
+    pub width: f64,
+    pub height: f64,
+    pub corner_radius: f64, // rounded-rectangle outline, 0 for square corners
+    pub edge: EdgeProfile,
+    pub depth: f64, // extent of the sides behind the front face, at least the edge size
+}
+
+/// Outline sample in the baffle plane (u, v) with its outward normal
+struct OutlinePoint {
+    position: (f64, f64),
+    normal: (f64, f64),
+}
+
+impl Baffle {
+    /// Whether (u, v) lies strictly inside the flat front face
+    fn clears(&self, (u, v): (f64, f64)) -> bool {
+        let inset = self.edge.size();
+        let (half_width, half_height) = (self.width / 2.0 - inset, self.height / 2.0 - inset);
+        let corner = (self.corner_radius - inset).max(0.0).min(half_width).min(half_height);
+        let (du, dv) = ((u.abs() - half_width + corner).max(0.0), (v.abs() - half_height + corner).max(0.0));
+        u.abs() < half_width && v.abs() < half_height && du.hypot(dv) <= corner
+    }
+
+    /// Counter-clockwise outline of the flat front face (inset by the edge), spaced by about `segment`
+    fn front_outline(&self, segment: f64) -> Vec<OutlinePoint> {
+        let inset = self.edge.size();
+        let half_width = self.width / 2.0 - inset;
+        let half_height = self.height / 2.0 - inset;
+        let corner = (self.corner_radius - inset)
+            .max(0.0)
+            .min(half_width)
+            .min(half_height);
+        // Square corners still get a fan of normals to sweep the edge around
+        let arc_steps = ((corner.max(inset) * PI / 2.0) / segment)
+            .ceil()
+            .max(1.0) as usize;
+
+        let mut outline = Vec::new();
+        for quadrant in 0..4 {
+            let (sign_u, sign_v) = [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)][quadrant];
+            let centre = (sign_u * (half_width - corner), sign_v * (half_height - corner));
+
+            for k in 0..=arc_steps {
+                let angle = (quadrant as f64 + k as f64 / arc_steps as f64) * PI / 2.0;
+                let normal = (angle.cos(), angle.sin());
+                outline.push(OutlinePoint {
+                    position: (centre.0 + corner * normal.0, centre.1 + corner * normal.1),
+                    normal,
+                });
+            }
+
+            // Straight side up to the next corner
+            let angle = (quadrant + 1) as f64 * PI / 2.0;
+            let normal = (angle.cos().round(), angle.sin().round());
+            let end = outline[outline.len() - 1].position;
+            let next_centre = [(-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0), (1.0, 1.0)][quadrant];
+            let next = (
+                next_centre.0 * (half_width - corner) + corner * normal.0,
+                next_centre.1 * (half_height - corner) + corner * normal.1,
+            );
+            let side_steps = ((next.0 - end.0).hypot(next.1 - end.1) / segment).ceil() as usize;
+            for k in 1..side_steps {
+                let t = k as f64 / side_steps as f64;
+                outline.push(OutlinePoint {
+                    position: (end.0 + t * (next.0 - end.0), end.1 + t * (next.1 - end.1)),
+                    normal,
+                });
+            }
+        }
+
+        outline
+    }
+}
+
+/// Adds the baffle around the mouth edge lying in `plane`: the flat front face stitched to the
+/// mouth vertices (tagged `Baffle`), the edge roundover or chamfer (`BaffleEdge`) and the sides going
+/// back by `depth` (`BaffleSide`). The back stays open.
+/// The mouth edge must be star-shaped around its centroid, which holds for waveguide mouths.
+/// Fails when no mouth edge lies in `plane`, or when the front face does not clear it.
+pub fn add_baffle(mesh: &mut Mesh, plane: &Plane, baffle: &Baffle) -> io::Result<()> {
+    let mouth = require_mouth_loop(mesh, plane)?;
+    let centre = mouth_centre(mesh, plane, &mouth);
+    let (u, v) = plane.axes();
+    let in_plane = |point: &CartesianPoint| {
+        let offset = *point - centre;
+        (offset.dot(&u), offset.dot(&v))
+    };
+
+    let mouth_2d: Vec<(f64, f64)> = mouth.iter().map(|&i| in_plane(&mesh.vertices[i])).collect();
+    if let Some(point) = mouth_2d.iter().find(|&&point| !baffle.clears(point)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} x {} baffle with a {:?} edge does not clear the mouth, reaching {:?} from its centre",
+                baffle.width, baffle.height, baffle.edge, point
+            ),
+        ));
+    }
+    let segment = (0..mouth.len())
+        .map(|k| {
+            let (a, b) = (mouth_2d[k], mouth_2d[(k + 1) % mouth.len()]);
+            (b.0 - a.0).hypot(b.1 - a.1)
+        })
+        .sum::<f64>()
+        / mouth.len() as f64;
+
+    // Walk the outline in the same rotational direction as the mouth edge
+    let signed_area: f64 = (0..mouth.len())
+        .map(|k| {
+            let (a, b) = (mouth_2d[k], mouth_2d[(k + 1) % mouth.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    let direction = signed_area.signum();
+    let mut outline = baffle.front_outline(segment);
+    if direction < 0.0 {
+        outline.reverse();
+    }
+
+    // Rings of vertices swept from the front outline around the edge and along the sides
+    let inset = baffle.edge.size();
+    let side_length = baffle.depth - inset;
+    let side_steps = if side_length > 0.0 {
+        (side_length / segment).ceil() as usize
+    } else {
+        0
+    };
+    let sweep: Vec<(f64, f64, SurfaceTag)> = baffle
+        .edge
+        .sweep(segment)
+        .into_iter()
+        .map(|(outward, back)| (outward, back, SurfaceTag::BaffleEdge))
+        .chain((1..=side_steps).map(|k| {
+            let t = k as f64 / side_steps as f64;
+            (inset, inset + t * side_length, SurfaceTag::BaffleSide)
+        }))
+        .collect();
+    let mut rings: Vec<(Vec<usize>, SurfaceTag)> = Vec::new();
+    for &(outward, back, tag) in &sweep {
+        let mut ring: Vec<usize> = Vec::with_capacity(outline.len());
+        let mut previous: Option<CartesianPoint> = None;
+        for point in &outline {
+            let position = centre
+                + u * (point.position.0 + outward * point.normal.0)
+                + v * (point.position.1 + outward * point.normal.1)
+                - plane.normal * back;
+            // Square corners repeat a position with several normals, share the vertex
+            match previous {
+                Some(last) if (position - last).norm() < 1e-9 => ring.push(ring[ring.len() - 1]),
+                _ => ring.push(mesh.add_vertex(position)),
+            }
+            previous = Some(position);
+        }
+        if ring.len() > 1 && (mesh.vertices[ring[0]] - mesh.vertices[ring[ring.len() - 1]]).norm() < 1e-9 {
+            let (first, last) = (ring[0], ring[ring.len() - 1]);
+            for index in ring.iter_mut().rev().take_while(|index| **index == last) {
+                *index = first;
+            }
+        }
+        rings.push((ring, tag));
+    }
+
+    // Front face: stitch the mouth edge to the outline by increasing polar angle
+    let front: Vec<usize> = {
+        let mut unique: Vec<usize> = rings[0].0.clone();
+        unique.dedup();
+        if unique.len() > 1 && unique[0] == unique[unique.len() - 1] {
+            unique.pop();
+        }
+        unique
+    };
+    let angle_of = |point: (f64, f64)| direction * point.1.atan2(point.0);
+    let front_2d: Vec<(f64, f64)> = front.iter().map(|&i| in_plane(&mesh.vertices[i])).collect();
+    let unwrap = |points: &[(f64, f64)], start: f64| -> Vec<f64> {
+        let mut angles = vec![start];
+        for k in 1..=points.len() {
+            let (a, b) = (points[k - 1], points[k % points.len()]);
+            let step = (angle_of(b) - angle_of(a)).rem_euclid(2.0 * PI);
+            angles.push(angles[k - 1] + step);
+        }
+        angles
+    };
+
+    let mouth_angles = unwrap(&mouth_2d, angle_of(mouth_2d[0]));
+    // Start the outline at the last point before the first mouth vertex
+    let offset = (0..front.len())
+        .min_by(|&a, &b| {
+            let gap = |k: usize| (mouth_angles[0] - angle_of(front_2d[k])).rem_euclid(2.0 * PI);
+            gap(a).total_cmp(&gap(b))
+        })
+        .unwrap_or(0);
+    let front: Vec<usize> = front[offset..].iter().chain(&front[..offset]).copied().collect();
+    let front_2d: Vec<(f64, f64)> = front_2d[offset..].iter().chain(&front_2d[..offset]).copied().collect();
+    let start = mouth_angles[0] - (mouth_angles[0] - angle_of(front_2d[0])).rem_euclid(2.0 * PI);
+    let front_angles = unwrap(&front_2d, start);
+
+    let (na, nb) = (mouth.len(), front.len());
+    let (mut i, mut j) = (0, 0);
+    while i < na || j < nb {
+        if j == nb || (i < na && mouth_angles[i + 1] <= front_angles[j + 1]) {
+            mesh.add_triangle([mouth[(i + 1) % na], mouth[i], front[j % nb]], SurfaceTag::Baffle);
+            i += 1;
+        } else {
+            mesh.add_triangle([mouth[i % na], front[j], front[(j + 1) % nb]], SurfaceTag::Baffle);
+            j += 1;
+        }
+    }
+
+    for pair in rings.windows(2) {
+        mesh.add_strip(&pair[0].0, &pair[1].0, pair[1].1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::mouth_loop;
+
+    /// Cone from r = 10 at z = 0 to r = 60 at z = 100, 16 angles
+    fn cone() -> Mesh {
+        let grid: Vec<Vec<CartesianPoint>> = (0..16)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 16.0;
+                (0..6).map(|j| CartesianPoint::from_cylindrical(10.0 + 10.0 * j as f64, theta, 20.0 * j as f64)).collect()
+            })
+            .collect();
+        Mesh::from_grid(&grid, true, SurfaceTag::Wall)
+    }
+
+    #[test]
+    fn baffle_leaves_only_the_back_open() {
+        let plane = Plane::at_z(100.0);
+        for edge in [EdgeProfile::Sharp, EdgeProfile::Roundover(10.0), EdgeProfile::Chamfer(5.0)] {
+            let baffle = Baffle { width: 200.0, height: 160.0, corner_radius: 20.0, edge, depth: 150.0 };
+            let mut mesh = cone();
+            add_baffle(&mut mesh, &plane, &baffle).unwrap();
+
+            // The throat and the back of the sides, nothing else
+            let loops = mesh.boundary_loops();
+            assert_eq!(loops.len(), 2);
+            let back = mouth_loop(&mesh, &Plane::at_z(-50.0)).unwrap();
+            assert!(back.iter().all(|&i| mesh.vertices[i].x.abs() <= 100.0 + 1e-9 && mesh.vertices[i].y.abs() <= 80.0 + 1e-9));
+            assert!(mesh.tags.contains(&SurfaceTag::Baffle) && mesh.tags.contains(&SurfaceTag::BaffleSide));
+        }
+    }
+
+    #[test]
+    fn baffle_must_clear_the_mouth() {
+        let plane = Plane::at_z(100.0);
+        let baffle = |width: f64, edge: EdgeProfile| Baffle { width, height: 160.0, corner_radius: 0.0, edge, depth: 50.0 };
+        assert!(add_baffle(&mut cone(), &plane, &baffle(130.0, EdgeProfile::Sharp)).is_ok());
+        // The roundover eats into the front face past the mouth
+        assert!(add_baffle(&mut cone(), &plane, &baffle(130.0, EdgeProfile::Roundover(10.0))).is_err());
+        assert!(add_baffle(&mut cone(), &Plane::at_z(90.0), &baffle(200.0, EdgeProfile::Sharp)).is_err());
+    }
+}
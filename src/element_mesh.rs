@@ -0,0 +1,273 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::mesh::{Mesh, SurfaceTag, Symmetry};
+use crate::models::AzimuthalSampling;
+use crate::sanitize::{place_profiles, repair_profiles, Defect};
+use crate::SPEED_OF_SOUND;
+use std::io;
+
+/// Target element size of a BEM surface mesh
+#[derive(Debug, Clone, Copy)]
+pub enum ElementSize {
+    /// Elements below a sixth of the wavelength at this frequency (Hz)
+    MaxFrequency(f64),
+    /// Longest element edge (mm)
+    MaxEdge(f64),
+}
+
+impl ElementSize {
+    pub fn max_edge(&self) -> f64 {
+        match *self {
+            ElementSize::MaxFrequency(frequency) => SPEED_OF_SOUND / frequency / 6.0,
+            ElementSize::MaxEdge(edge) => edge,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementShape {
+    Triangle,
+    Quad,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ElementMeshOptions {
+    pub size: ElementSize,
+    pub throat_ratio: f64, // axial element length at the throat relative to the mouth, 1 for no grading
+    pub shape: ElementShape,
+    pub symmetry: Symmetry,
+}
+
+impl ElementMeshOptions {
+    /// Rejects element sizes and throat ratios that are not positive and finite
+    pub fn validate(&self) -> io::Result<()> {
+        let max_edge = self.size.max_edge();
+        if !(max_edge.is_finite() && max_edge > 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("element size {:?} gives an edge length of {} mm", self.size, max_edge),
+            ));
+        }
+        if !(self.throat_ratio.is_finite() && self.throat_ratio > 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("throat ratio {} must be positive", self.throat_ratio),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Surface mesh of triangle and quad elements with a surface tag per element
+#[derive(Debug, Clone, Default)]
+pub struct ElementMesh {
+    pub vertices: Vec<CartesianPoint>,
+    pub elements: Vec<Vec<usize>>,
+    pub tags: Vec<SurfaceTag>,
+    pub symmetry: Symmetry,
+}
+
+impl ElementMesh {
+    /// Triangle mesh, quads split along their 0-2 diagonal
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh {
+            vertices: self.vertices.clone(),
+            symmetry: self.symmetry,
+            ..Mesh::default()
+        };
+        for (element, &tag) in self.elements.iter().zip(&self.tags) {
+            for k in 1..element.len() - 1 {
+                mesh.add_triangle([element[0], element[k], element[k + 1]], tag);
+            }
+        }
+        mesh
+    }
+}
+
+/// Resamples every profile to the same number of points, spaced along the (z, r) arc length
+/// from `throat_edge` at the throat growing linearly to `max_edge` at the mouth. Points sit
+/// at the same arc-length fractions on every profile, measured on the longest one.
+pub fn resample_profiles(
+    profiles: &[Vec<ProfilePoint>],
+    max_edge: f64,
+    throat_edge: f64,
+) -> Vec<Vec<ProfilePoint>> {
+    let arc_lengths: Vec<Vec<f64>> = profiles
+        .iter()
+        .map(|profile| {
+            let mut arc_length = vec![0.0];
+            for pair in profile.windows(2) {
+                let step = (pair[1].z - pair[0].z).hypot(pair[1].r - pair[0].r);
+                arc_length.push(arc_length[arc_length.len() - 1] + step);
+            }
+            arc_length
+        })
+        .collect();
+    let longest = arc_lengths
+        .iter()
+        .map(|arc_length| arc_length[arc_length.len() - 1])
+        .fold(0.0, f64::max);
+
+    // Element count from integrating 1 / h(s) with h growing linearly along the profile
+    let growth = (max_edge - throat_edge) / longest;
+    let fraction = |t: f64| {
+        if growth.abs() < 1e-12 {
+            t
+        } else {
+            ((1.0 + growth * longest / throat_edge).powf(t) - 1.0) * throat_edge / (growth * longest)
+        }
+    };
+    let span = if growth.abs() < 1e-12 {
+        longest / throat_edge
+    } else {
+        (max_edge / throat_edge).ln() / growth
+    };
+    let steps = span.ceil().max(1.0) as usize;
+
+    profiles
+        .iter()
+        .zip(&arc_lengths)
+        .map(|(profile, arc_length)| {
+            let total = arc_length[arc_length.len() - 1];
+            let mut segment = 0;
+            (0..=steps)
+                .map(|k| {
+                    let target = total * fraction(k as f64 / steps as f64);
+                    while segment + 2 < arc_length.len() && arc_length[segment + 1] < target {
+                        segment += 1;
+                    }
+                    let (a, b) = (profile[segment], profile[segment + 1]);
+                    let length = arc_length[segment + 1] - arc_length[segment];
+                    let t = if length > 0.0 {
+                        ((target - arc_length[segment]) / length).clamp(0.0, 1.0)
+                    } else {
+                        0.0
+                    };
+                    ProfilePoint {
+                        z: a.z + t * (b.z - a.z),
+                        r: a.r + t * (b.r - a.r),
+                        theta: a.theta,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Meshes a family of placed profiles of equal length into quads, or into triangles with the
+/// quad diagonal alternating in a checkerboard so no direction is favoured. The profiles close
+/// the turn for `Symmetry::Full` and span the sector otherwise.
+/// Winding matches `triangulate_profiles`.
+pub fn mesh_profiles(
+    profiles: &[Vec<CartesianPoint>],
+    shape: ElementShape,
+    symmetry: Symmetry,
+    tag: SurfaceTag,
+) -> ElementMesh {
+    let mut mesh = ElementMesh {
+        symmetry,
+        ..ElementMesh::default()
+    };
+    let points = profiles.iter().map(Vec::len).min().unwrap_or(0);
+    for profile in profiles {
+        mesh.vertices.extend_from_slice(&profile[..points]);
+    }
+    let index = |profile: usize, point: usize| (profile % profiles.len()) * points + point;
+
+    let sectors = if symmetry == Symmetry::Full { profiles.len() } else { profiles.len().saturating_sub(1) };
+    for i in 0..sectors {
+        for j in 0..points.saturating_sub(1) {
+            let (p0, p1, p2, p3) = (index(i, j), index(i, j + 1), index(i + 1, j), index(i + 1, j + 1));
+            let elements = match shape {
+                ElementShape::Quad => vec![vec![p0, p2, p3, p1]],
+                ElementShape::Triangle if (i + j) % 2 == 0 => vec![vec![p0, p2, p1], vec![p1, p2, p3]],
+                ElementShape::Triangle => vec![vec![p0, p2, p3], vec![p0, p3, p1]],
+            };
+            for element in elements {
+                mesh.elements.push(element);
+                mesh.tags.push(tag);
+            }
+        }
+    }
+
+    mesh
+}
+
+/// Number of profile angles giving mouth edges of at most `max_edge`, a multiple of 4 so the
+/// grid keeps the symmetry planes
+pub fn azimuth_steps_for(mouth: &[CartesianPoint], max_edge: f64) -> usize {
+    let perimeter: f64 = (0..mouth.len())
+        .map(|k| (mouth[(k + 1) % mouth.len()] - mouth[k]).norm())
+        .sum();
+    ((perimeter / max_edge / 4.0).ceil().max(1.0) as usize) * 4
+}
+
+/// BEM surface mesh of a wall with elements sized by `options`, angles spread evenly along the
+/// mouth contour, and the defects left by repairing its grid. `profiles` generates the profiles
+/// of a sector, finely sampled along z, its errors returned; `place` maps them to 3D.
+pub fn generate_element_mesh(
+    options: &ElementMeshOptions,
+    profiles: impl Fn(AzimuthalSampling, Symmetry) -> io::Result<Vec<Vec<ProfilePoint>>>,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+) -> io::Result<(ElementMesh, Vec<Defect>)> {
+    options.validate()?;
+    let max_edge = options.size.max_edge();
+    let mouth: Vec<CartesianPoint> = profiles(AzimuthalSampling::Uniform(64), Symmetry::Full)?
+        .iter()
+        .map(|profile| place(&profile[profile.len() - 1]))
+        .collect();
+    let azimuth = AzimuthalSampling::MouthArcLength(azimuth_steps_for(&mouth, max_edge));
+
+    let mut resampled = resample_profiles(
+        &profiles(azimuth, options.symmetry)?,
+        max_edge,
+        options.throat_ratio * max_edge,
+    );
+    let defects = repair_profiles(&mut resampled, options.symmetry, &place, |_, _| None);
+    let placed = place_profiles(&resampled, options.symmetry, place);
+    Ok((mesh_profiles(&placed, options.shape, options.symmetry, SurfaceTag::Wall), defects))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        let options = ElementMeshOptions {
+            size: ElementSize::MaxEdge(10.0),
+            throat_ratio: 0.5,
+            shape: ElementShape::Quad,
+            symmetry: Symmetry::Full,
+        };
+        assert!(options.validate().is_ok());
+        assert!(ElementMeshOptions { throat_ratio: 0.0, ..options }.validate().is_err());
+        assert!(ElementMeshOptions { size: ElementSize::MaxFrequency(0.0), ..options }.validate().is_err());
+        assert!(ElementMeshOptions { size: ElementSize::MaxEdge(-1.0), ..options }.validate().is_err());
+    }
+
+    #[test]
+    fn cylinder_elements_stay_below_the_edge_length() {
+        let options = ElementMeshOptions {
+            size: ElementSize::MaxEdge(10.0),
+            throat_ratio: 1.0,
+            shape: ElementShape::Quad,
+            symmetry: Symmetry::Full,
+        };
+        let cylinder = |azimuth: AzimuthalSampling, symmetry: Symmetry| {
+            azimuth
+                .positions(symmetry, |theta| Ok(vec![ProfilePoint { z: 100.0, r: 50.0, theta }]))?
+                .into_iter()
+                .map(|theta| Ok((0..=100).map(|k| ProfilePoint { z: k as f64, r: 50.0, theta }).collect()))
+                .collect()
+        };
+        let place = |point: &ProfilePoint| CartesianPoint::from_cylindrical(point.r, point.theta, point.z);
+        let (mesh, defects) = generate_element_mesh(&options, cylinder, place).unwrap();
+        assert!(defects.is_empty());
+        for element in &mesh.elements {
+            for k in 0..element.len() {
+                let edge = mesh.vertices[element[(k + 1) % element.len()]] - mesh.vertices[element[k]];
+                assert!(edge.norm() <= 10.0 + 1e-9);
+            }
+        }
+    }
+}
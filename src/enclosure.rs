@@ -0,0 +1,86 @@
+use crate::baffle::{add_baffle, Baffle, EdgeProfile};
+use crate::mesh::{Mesh, SurfaceTag};
+use crate::throat::{add_throat_cap, ThroatCap};
+use crate::trim::{mouth_loop, Plane};
+use std::io;
+
+/// Closed box with the waveguide mouth flush in the centre of its front baffle
+#[derive(Debug, Clone, Copy)]
+pub struct Enclosure {
+    pub width: f64,
+    pub height: f64,
+    pub depth: f64,
+    pub corner_radius: f64, // rounding of the side edges seen from the front, 0 for a plain box
+    pub edge: EdgeProfile,  // front edges
+    pub source: ThroatCap,  // driving surface closing the throat
+}
+
+/// Builds the enclosure around a waveguide wall whose mouth edge lies in `plane` and throat edge
+/// in `throat`: front baffle, edge and sides as in `add_baffle`, the source cap over the throat
+/// and a flat back panel (`EnclosureBack`), giving a closed watertight mesh with outward normals.
+/// The box must be deep enough to contain the waveguide. Fails like `add_baffle` and
+/// `add_throat_cap`.
+pub fn generate_enclosure(wall: &Mesh, plane: &Plane, throat: &Plane, enclosure: &Enclosure) -> io::Result<Mesh> {
+    let mut mesh = wall.clone();
+    add_baffle(
+        &mut mesh,
+        plane,
+        &Baffle {
+            width: enclosure.width,
+            height: enclosure.height,
+            corner_radius: enclosure.corner_radius,
+            edge: enclosure.edge,
+            depth: enclosure.depth,
+        },
+    )?;
+    add_throat_cap(&mut mesh, throat, &enclosure.source)?;
+
+    let back_plane = Plane {
+        point: plane.point - plane.normal * enclosure.depth,
+        normal: plane.normal,
+    };
+    if let Some(back) = mouth_loop(&mesh, &back_plane) {
+        mesh.add_cap(&back, SurfaceTag::EnclosureBack);
+    }
+    // Normals point out of the box, into the air
+    if mesh.signed_volume() < 0.0 {
+        mesh.flip();
+    }
+
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry_types::CartesianPoint;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn enclosure_is_watertight() {
+        // Cone from r = 10 at z = 0 to r = 60 at z = 100, 16 angles
+        let grid: Vec<Vec<CartesianPoint>> = (0..16)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 16.0;
+                (0..6).map(|j| CartesianPoint::from_cylindrical(10.0 + 10.0 * j as f64, theta, 20.0 * j as f64)).collect()
+            })
+            .collect();
+        let wall = Mesh::from_grid(&grid, true, SurfaceTag::Wall);
+        for source in [ThroatCap::Flat, ThroatCap::Spherical { radius: 20.0 }] {
+            let enclosure = Enclosure {
+                width: 200.0,
+                height: 160.0,
+                depth: 150.0,
+                corner_radius: 20.0,
+                edge: EdgeProfile::Roundover(10.0),
+                source,
+            };
+            let mesh = generate_enclosure(&wall, &Plane::at_z(100.0), &Plane::at_z(0.0), &enclosure).unwrap();
+            assert!(mesh.boundary_loops().is_empty());
+            assert!(mesh.tags.contains(&SurfaceTag::EnclosureBack) && mesh.tags.contains(&SurfaceTag::Source));
+            // Box less the horn, give or take the faceting of the rounded corners
+            let volume = mesh.signed_volume();
+            assert!(volume > 0.0 && volume < 200.0 * 160.0 * 150.0);
+        }
+    }
+}
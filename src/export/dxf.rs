@@ -0,0 +1,351 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::nurbs::BSplineCurve;
+use crate::record::DesignRecord;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// DXF entity the curves are written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DxfEntity {
+    /// 3D polyline through the points
+    Polyline,
+    /// Cubic spline interpolating the points
+    Spline,
+}
+
+/// Curve of a DXF drawing, drawn on the layer named `layer`
+#[derive(Debug, Clone)]
+pub struct DxfCurve {
+    pub layer: String,
+    pub points: Vec<CartesianPoint>,
+}
+
+/// Profile drawn in its own plane for a sketch: x = r, y = z
+pub fn profile_sketch(profile: &[ProfilePoint]) -> Vec<CartesianPoint> {
+    profile
+        .iter()
+        .map(|point| CartesianPoint { x: point.r, y: point.z, z: 0.0 })
+        .collect()
+}
+
+/// DXF file being assembled as group code / value pairs
+struct DxfWriter {
+    pairs: Vec<(u16, String)>,
+    handle: usize,
+}
+
+impl DxfWriter {
+    fn pair(&mut self, code: u16, value: impl ToString) {
+        self.pairs.push((code, value.to_string()));
+    }
+
+    /// Fresh handle, for objects referenced before they are written
+    fn next_handle(&mut self) -> String {
+        self.handle += 1;
+        format!("{:X}", self.handle)
+    }
+
+    /// Object start with `handle` (group code `handle_code`), owned by `owner` ("0" for none)
+    fn start_as(&mut self, kind: &str, handle_code: u16, handle: &str, owner: &str) {
+        self.pair(0, kind);
+        self.pair(handle_code, handle);
+        self.pair(330, owner);
+    }
+
+    /// Object start with a fresh handle, returned
+    fn start(&mut self, kind: &str, owner: &str) -> String {
+        let handle = self.next_handle();
+        self.start_as(kind, 5, &handle, owner);
+        handle
+    }
+
+    /// Symbol table start, returning its handle to own the `count` records that follow
+    fn table(&mut self, name: &str, count: usize) -> String {
+        // The name comes before the handle in a table header
+        let handle = self.next_handle();
+        self.pair(0, "TABLE");
+        self.pair(2, name);
+        self.pair(5, &handle);
+        self.pair(330, "0");
+        self.pair(100, "AcDbSymbolTable");
+        self.pair(70, count);
+        // The dimension styles also have their own subclass, and 105 handles (see `record`)
+        if name == "DIMSTYLE" {
+            self.pair(100, "AcDbDimStyleTable");
+        }
+        handle
+    }
+
+    /// Table record start, with its subclass and name
+    fn record(&mut self, kind: &str, subclass: &str, name: &str, owner: &str) -> String {
+        let handle = if kind == "DIMSTYLE" {
+            let handle = self.next_handle();
+            self.start_as(kind, 105, &handle, owner);
+            handle
+        } else {
+            self.start(kind, owner)
+        };
+        self.pair(100, "AcDbSymbolTableRecord");
+        self.pair(100, subclass);
+        self.pair(2, name);
+        self.pair(70, 0);
+        handle
+    }
+
+    fn point(&mut self, code: u16, point: &CartesianPoint) {
+        self.pair(code, point.x);
+        self.pair(code + 10, point.y);
+        self.pair(code + 20, point.z);
+    }
+
+    fn polyline(&mut self, curve: &DxfCurve, owner: &str) {
+        let polyline = self.start("POLYLINE", owner);
+        self.pair(100, "AcDbEntity");
+        self.pair(8, &curve.layer);
+        self.pair(100, "AcDb3dPolyline");
+        self.pair(66, 1);
+        self.point(10, &CartesianPoint { x: 0.0, y: 0.0, z: 0.0 });
+        self.pair(70, 8);
+        for point in &curve.points {
+            self.start("VERTEX", &polyline);
+            self.pair(100, "AcDbEntity");
+            self.pair(8, &curve.layer);
+            self.pair(100, "AcDbVertex");
+            self.pair(100, "AcDb3dPolylineVertex");
+            self.point(10, point);
+            self.pair(70, 32);
+        }
+        self.start("SEQEND", &polyline);
+        self.pair(100, "AcDbEntity");
+        self.pair(8, &curve.layer);
+    }
+
+    fn spline(&mut self, curve: &DxfCurve, owner: &str) -> io::Result<()> {
+        let spline = BSplineCurve::interpolate(&curve.points)?;
+        self.start("SPLINE", owner);
+        self.pair(100, "AcDbEntity");
+        self.pair(8, &curve.layer);
+        self.pair(100, "AcDbSpline");
+        self.pair(70, 0);
+        self.pair(71, spline.degree);
+        self.pair(72, spline.knots.len());
+        self.pair(73, spline.control_points.len());
+        self.pair(74, 0);
+        self.pair(42, 1e-10);
+        self.pair(43, 1e-10);
+        for knot in &spline.knots {
+            self.pair(40, knot);
+        }
+        for point in &spline.control_points {
+            self.point(10, point);
+        }
+        Ok(())
+    }
+}
+
+/// Writes the curves to a DXF (R2000) drawing in millimetres, one layer per distinct layer name
+/// besides the default layer "0", with the tables, blocks and objects AutoCAD requires.
+/// The design `record` goes in a leading comment.
+pub fn write_dxf(
+    curves: &[DxfCurve],
+    entity: DxfEntity,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let mut layers: Vec<&str> = vec!["0"];
+    for curve in curves {
+        if !layers.contains(&curve.layer.as_str()) {
+            layers.push(&curve.layer);
+        }
+    }
+
+    let mut dxf = DxfWriter { pairs: Vec::new(), handle: 0 };
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "CLASSES");
+    dxf.pair(0, "ENDSEC");
+
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "TABLES");
+
+    let table = dxf.table("VPORT", 1);
+    dxf.record("VPORT", "AcDbViewportTableRecord", "*Active", &table);
+    dxf.point(10, &CartesianPoint { x: 0.0, y: 0.0, z: 0.0 });
+    dxf.pair(11, 1.0);
+    dxf.pair(21, 1.0);
+    dxf.pair(12, 0.0);
+    dxf.pair(22, 0.0);
+    dxf.pair(40, 1000.0);
+    dxf.pair(41, 1.5);
+    dxf.pair(0, "ENDTAB");
+
+    let table = dxf.table("LTYPE", 3);
+    for (name, description) in [("ByBlock", ""), ("ByLayer", ""), ("Continuous", "Solid line")] {
+        dxf.record("LTYPE", "AcDbLinetypeTableRecord", name, &table);
+        dxf.pair(3, description);
+        dxf.pair(72, 65);
+        dxf.pair(73, 0);
+        dxf.pair(40, 0.0);
+    }
+    dxf.pair(0, "ENDTAB");
+
+    let table = dxf.table("LAYER", layers.len());
+    for (index, layer) in layers.iter().enumerate() {
+        dxf.record("LAYER", "AcDbLayerTableRecord", layer, &table);
+        dxf.pair(62, index % 7 + 1);
+        dxf.pair(6, "Continuous");
+    }
+    dxf.pair(0, "ENDTAB");
+
+    let table = dxf.table("STYLE", 1);
+    dxf.record("STYLE", "AcDbTextStyleTableRecord", "Standard", &table);
+    dxf.pair(40, 0.0);
+    dxf.pair(41, 1.0);
+    dxf.pair(50, 0.0);
+    dxf.pair(71, 0);
+    dxf.pair(42, 2.5);
+    dxf.pair(3, "txt");
+    dxf.pair(4, "");
+    dxf.pair(0, "ENDTAB");
+
+    for name in ["VIEW", "UCS"] {
+        dxf.table(name, 0);
+        dxf.pair(0, "ENDTAB");
+    }
+
+    let table = dxf.table("APPID", 1);
+    dxf.record("APPID", "AcDbRegAppTableRecord", "ACAD", &table);
+    dxf.pair(0, "ENDTAB");
+
+    let table = dxf.table("DIMSTYLE", 1);
+    dxf.record("DIMSTYLE", "AcDbDimStyleTableRecord", "Standard", &table);
+    dxf.pair(0, "ENDTAB");
+
+    let table = dxf.table("BLOCK_RECORD", 2);
+    let spaces: Vec<(&str, String)> = ["*Model_Space", "*Paper_Space"]
+        .into_iter()
+        .map(|name| (name, dxf.record("BLOCK_RECORD", "AcDbBlockTableRecord", name, &table)))
+        .collect();
+    dxf.pair(0, "ENDTAB");
+    dxf.pair(0, "ENDSEC");
+
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "BLOCKS");
+    for (name, owner) in &spaces {
+        dxf.start("BLOCK", owner);
+        dxf.pair(100, "AcDbEntity");
+        if *name == "*Paper_Space" {
+            dxf.pair(67, 1);
+        }
+        dxf.pair(8, "0");
+        dxf.pair(100, "AcDbBlockBegin");
+        dxf.pair(2, name);
+        dxf.pair(70, 0);
+        dxf.point(10, &CartesianPoint { x: 0.0, y: 0.0, z: 0.0 });
+        dxf.pair(3, name);
+        dxf.pair(1, "");
+        dxf.start("ENDBLK", owner);
+        dxf.pair(100, "AcDbEntity");
+        if *name == "*Paper_Space" {
+            dxf.pair(67, 1);
+        }
+        dxf.pair(8, "0");
+        dxf.pair(100, "AcDbBlockEnd");
+    }
+    dxf.pair(0, "ENDSEC");
+
+    let model_space = &spaces[0].1;
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "ENTITIES");
+    for curve in curves.iter().filter(|curve| curve.points.len() >= 2) {
+        match entity {
+            DxfEntity::Polyline => dxf.polyline(curve, model_space),
+            DxfEntity::Spline => dxf.spline(curve, model_space)?,
+        }
+    }
+    dxf.pair(0, "ENDSEC");
+
+    // Root dictionary, holding the (empty) group dictionary
+    dxf.pair(0, "SECTION");
+    dxf.pair(2, "OBJECTS");
+    let root = dxf.next_handle();
+    let groups = dxf.next_handle();
+    dxf.start_as("DICTIONARY", 5, &root, "0");
+    dxf.pair(100, "AcDbDictionary");
+    dxf.pair(281, 1);
+    dxf.pair(3, "ACAD_GROUP");
+    dxf.pair(350, &groups);
+    dxf.start_as("DICTIONARY", 5, &groups, &root);
+    dxf.pair(100, "AcDbDictionary");
+    dxf.pair(281, 1);
+    dxf.pair(0, "ENDSEC");
+    dxf.pair(0, "EOF");
+
+    let mut file = BufWriter::new(File::create(path)?);
+    if let Some(record) = record {
+        writeln!(file, "999\ndesign {}", record.to_json())?;
+    }
+    writeln!(file, "0\nSECTION\n2\nHEADER")?;
+    writeln!(file, "9\n$ACADVER\n1\nAC1015")?;
+    writeln!(file, "9\n$INSUNITS\n70\n4")?;
+    writeln!(file, "9\n$HANDSEED\n5\n{:X}", dxf.handle + 1)?;
+    writeln!(file, "0\nENDSEC")?;
+    for (code, value) in &dxf.pairs {
+        writeln!(file, "{}\n{}", code, value)?;
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(path: &Path) -> Vec<(String, String)> {
+        let text = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        lines.chunks(2).map(|pair| (pair[0].trim().to_string(), pair[1].to_string())).collect()
+    }
+
+    #[test]
+    fn drawing_is_complete() {
+        let curve = |layer: &str| DxfCurve {
+            layer: layer.to_string(),
+            points: (0..6).map(|k| CartesianPoint { x: k as f64, y: (k * k) as f64, z: 0.0 }).collect(),
+        };
+        for entity in [DxfEntity::Polyline, DxfEntity::Spline] {
+            let path = std::env::temp_dir().join(format!("compression_waveguide_{:?}.dxf", entity));
+            write_dxf(&[curve("a"), curve("b"), curve("a")], entity, None, &path).unwrap();
+            let pairs = pairs(&path);
+
+            let sections: Vec<&str> = pairs
+                .windows(2)
+                .filter(|window| window[0].1 == "SECTION")
+                .map(|window| window[1].1.as_str())
+                .collect();
+            assert_eq!(sections, ["HEADER", "CLASSES", "TABLES", "BLOCKS", "ENTITIES", "OBJECTS"]);
+            let tables: Vec<&str> = pairs
+                .windows(2)
+                .filter(|window| window[0].1 == "TABLE")
+                .map(|window| window[1].1.as_str())
+                .collect();
+            assert_eq!(tables, ["VPORT", "LTYPE", "LAYER", "STYLE", "VIEW", "UCS", "APPID", "DIMSTYLE", "BLOCK_RECORD"]);
+            assert_eq!(pairs.iter().filter(|(code, value)| code == "0" && value == "LAYER").count(), 3);
+
+            // Handles are unique and below the seed, owners are written objects
+            let handles: Vec<usize> = pairs
+                .iter()
+                .filter(|(code, _)| code == "5" || code == "105")
+                .map(|(_, value)| usize::from_str_radix(value, 16).unwrap())
+                .collect();
+            let seed = handles[0];
+            let handles = &handles[1..];
+            assert!(handles.iter().all(|&handle| handle < seed));
+            assert!((1..handles.len()).all(|k| !handles[..k].contains(&handles[k])));
+            for (_, owner) in pairs.iter().filter(|(code, _)| code == "330") {
+                let owner = usize::from_str_radix(owner, 16).unwrap();
+                assert!(owner == 0 || handles.contains(&owner));
+            }
+            assert_eq!(pairs.last().unwrap().1, "EOF");
+        }
+    }
+}
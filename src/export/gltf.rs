@@ -0,0 +1,177 @@
+use crate::mesh::{Mesh, SurfaceTag};
+use crate::record::DesignRecord;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Quoted and escaped JSON string
+fn json_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap()
+}
+
+/// Writes the mesh as binary glTF 2.0 (.glb): one named node and mesh per surface tag, all
+/// sharing the positions and vertex normals. Coordinates stay in millimetres with z along the
+/// axis; the nodes scale them to the metres glTF expects. The design `record` goes in the asset
+/// extras.
+pub fn write_glb(mesh: &Mesh, name: &str, record: Option<&DesignRecord>, path: impl AsRef<Path>) -> io::Result<()> {
+    let normals = mesh.vertex_normals();
+    let count = mesh.vertices.len();
+
+    // Binary buffer: positions, normals, then the indices of each tag
+    let mut buffer: Vec<u8> = Vec::new();
+    for vertex in &mesh.vertices {
+        for value in [vertex.x, vertex.y, vertex.z] {
+            buffer.extend((value as f32).to_le_bytes());
+        }
+    }
+    for normal in &normals {
+        for value in [normal.x, normal.y, normal.z] {
+            buffer.extend((value as f32).to_le_bytes());
+        }
+    }
+    let mut buffer_views = vec![
+        format!(r#"{{"buffer":0,"byteOffset":0,"byteLength":{},"byteStride":12,"target":{}}}"#, 12 * count, ARRAY_BUFFER),
+        format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"byteStride":12,"target":{}}}"#,
+            12 * count,
+            12 * count,
+            ARRAY_BUFFER
+        ),
+    ];
+
+    let (min, max) = mesh.vertices.iter().fold(
+        ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+        |(min, max), vertex| {
+            let point = [vertex.x as f32, vertex.y as f32, vertex.z as f32];
+            (
+                [0, 1, 2].map(|k| min[k].min(point[k])),
+                [0, 1, 2].map(|k| max[k].max(point[k])),
+            )
+        },
+    );
+    let mut accessors = vec![
+        format!(
+            r#"{{"bufferView":0,"componentType":{},"count":{},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            FLOAT, count, min[0], min[1], min[2], max[0], max[1], max[2]
+        ),
+        format!(r#"{{"bufferView":1,"componentType":{},"count":{},"type":"VEC3"}}"#, FLOAT, count),
+    ];
+
+    let mut meshes = Vec::new();
+    let mut nodes = Vec::new();
+    for tag in SurfaceTag::ALL {
+        let indices: Vec<u32> = mesh
+            .triangles
+            .iter()
+            .zip(&mesh.tags)
+            .filter(|(_, &other)| other == tag)
+            .flat_map(|(face, _)| face.map(|index| index as u32))
+            .collect();
+        if indices.is_empty() {
+            continue;
+        }
+        let offset = buffer.len();
+        buffer.extend(indices.iter().flat_map(|index| index.to_le_bytes()));
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            offset,
+            4 * indices.len(),
+            ELEMENT_ARRAY_BUFFER
+        ));
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"SCALAR"}}"#,
+            buffer_views.len() - 1,
+            UNSIGNED_INT,
+            indices.len()
+        ));
+        meshes.push(format!(
+            r#"{{"name":{},"primitives":[{{"attributes":{{"POSITION":0,"NORMAL":1}},"indices":{},"mode":4}}]}}"#,
+            json_string(tag.name()),
+            accessors.len() - 1
+        ));
+        nodes.push(format!(
+            r#"{{"name":{},"mesh":{},"scale":[0.001,0.001,0.001]}}"#,
+            json_string(tag.name()),
+            meshes.len() - 1
+        ));
+    }
+
+    let extras = record.map(|record| format!(r#","extras":{{"design":{}}}"#, record.to_json())).unwrap_or_default();
+    let json = format!(
+        r#"{{"asset":{{"version":"2.0","generator":"compression-waveguide {}"{}}},"scene":0,"scenes":[{{"name":{},"nodes":[{}]}}],"nodes":[{}],"meshes":[{}],"accessors":[{}],"bufferViews":[{}],"buffers":[{{"byteLength":{}}}]}}"#,
+        env!("CARGO_PKG_VERSION"),
+        extras,
+        json_string(name),
+        (0..nodes.len()).map(|i| i.to_string()).collect::<Vec<_>>().join(","),
+        nodes.join(","),
+        meshes.join(","),
+        accessors.join(","),
+        buffer_views.join(","),
+        buffer.len()
+    );
+
+    // Chunks are padded to 4 bytes, JSON with spaces and binary with zeros
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    buffer.resize(buffer.len().next_multiple_of(4), 0);
+    let total = 12 + 8 + json.len() + 8 + buffer.len();
+
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(b"glTF")?;
+    file.write_all(&2u32.to_le_bytes())?;
+    file.write_all(&(total as u32).to_le_bytes())?;
+    file.write_all(&(json.len() as u32).to_le_bytes())?;
+    file.write_all(b"JSON")?;
+    file.write_all(&json)?;
+    file.write_all(&(buffer.len() as u32).to_le_bytes())?;
+    file.write_all(b"BIN\0")?;
+    file.write_all(&buffer)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry_types::CartesianPoint;
+
+    #[test]
+    fn chunks_are_aligned_and_json_parses() {
+        let point = |x: f64, y: f64, z: f64| CartesianPoint { x, y, z };
+        let mut mesh = Mesh::from_triangles(&[[point(0.0, 0.0, 0.0), point(1.0, 0.0, 0.0), point(0.0, 1.0, 0.0)]], SurfaceTag::Wall);
+        let apex = mesh.add_vertex(point(0.0, 0.0, 1.0));
+        mesh.add_triangle([0, 2, apex], SurfaceTag::Source);
+        let name = "paroi \"ancrée\" \\ 1";
+        let path = std::env::temp_dir().join("compression_waveguide_chunks.glb");
+        write_glb(&mesh, name, None, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        assert_eq!(&bytes[..4], b"glTF");
+        assert_eq!((word(4), word(8)), (2, bytes.len()));
+        let json_length = word(12);
+        assert_eq!(&bytes[16..20], b"JSON");
+        let binary = 20 + json_length;
+        assert_eq!((json_length % 4, binary % 4), (0, 0));
+        assert_eq!(&bytes[binary + 4..binary + 8], b"BIN\0");
+        assert_eq!(word(binary) % 4, 0);
+        assert_eq!(binary + 8 + word(binary), bytes.len());
+
+        let json: serde_json::Value = serde_json::from_slice(&bytes[20..binary]).unwrap();
+        assert_eq!(json["scenes"][0]["name"], name);
+        let names: Vec<&str> = json["nodes"].as_array().unwrap().iter().map(|node| node["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["wall", "source"]);
+        // Every view inside the buffer, which fits in the binary chunk
+        let buffer_length = json["buffers"][0]["byteLength"].as_u64().unwrap() as usize;
+        assert!(buffer_length <= word(binary));
+        for view in json["bufferViews"].as_array().unwrap() {
+            let end = view["byteOffset"].as_u64().unwrap() + view["byteLength"].as_u64().unwrap();
+            assert!(end as usize <= buffer_length);
+        }
+        assert_eq!(json["accessors"][0]["count"], 4);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::nurbs::BSplineSurface;
+use crate::record::DesignRecord;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Text with its non-ASCII characters as JSON \u escapes (UTF-16 units): IGES files are ASCII
+/// with fixed byte columns, and the escapes keep the embedded record valid JSON
+fn ascii(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if character.is_ascii() {
+            escaped.push(character);
+        } else {
+            for unit in character.encode_utf16(&mut [0; 2]) {
+                escaped.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    escaped
+}
+
+/// IGES string constant
+fn hollerith(text: &str) -> String {
+    let text = ascii(text);
+    format!("{}H{}", text.len(), text)
+}
+
+fn real(value: f64) -> String {
+    format!("{:.12E}", value)
+}
+
+/// Current UTC time as YYYYMMDD.HHNNSS
+fn timestamp() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs()) as i64;
+    let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // Civil date from days since 1970-01-01 (proleptic Gregorian)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}.{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Parameters of a rational B-spline surface entity (type 128), all weights 1
+fn surface_parameters(surface: &BSplineSurface) -> Vec<String> {
+    let (count_u, count_v) = (surface.control_points.len(), surface.control_points[0].len());
+    let mut parameters = vec![
+        "128".to_string(),
+        (count_u - 1).to_string(),
+        (count_v - 1).to_string(),
+        surface.degree_u.to_string(),
+        surface.degree_v.to_string(),
+        "1".to_string(), // closed around the axis
+        "0".to_string(),
+        "1".to_string(), // polynomial
+        "0".to_string(),
+        "0".to_string(),
+    ];
+    parameters.extend(surface.knots_u.iter().map(|&knot| real(knot)));
+    parameters.extend(surface.knots_v.iter().map(|&knot| real(knot)));
+    parameters.extend(std::iter::repeat_n(real(1.0), count_u * count_v));
+    // Control points with u varying fastest
+    for j in 0..count_v {
+        for row in &surface.control_points {
+            let point = row[j];
+            parameters.extend([real(point.x), real(point.y), real(point.z)]);
+        }
+    }
+    parameters.extend([
+        real(surface.knots_u[surface.degree_u]),
+        real(surface.knots_u[count_u]),
+        real(surface.knots_v[surface.degree_v]),
+        real(surface.knots_v[count_v]),
+    ]);
+    parameters
+}
+
+/// Fixed-column lines of a free-format section: `width` columns of data, no token split
+fn wrap(tokens: &[String], width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for (index, token) in tokens.iter().enumerate() {
+        let delimiter = if index + 1 == tokens.len() { ';' } else { ',' };
+        let current = lines.last_mut().unwrap();
+        if !current.is_empty() && current.len() + token.len() + 1 > width {
+            lines.push(String::new());
+        }
+        let current = lines.last_mut().unwrap();
+        current.push_str(token);
+        current.push(delimiter);
+    }
+    lines
+}
+
+/// Writes B-spline surfaces, as fitted by `fit_surfaces`, to an IGES 5.3 file in millimetres,
+/// one rational B-spline surface entity (type 128) each. The design `record` follows the name in
+/// the start section, cut into lines. Non-ASCII text is written as JSON \u escapes.
+pub fn write_iges(
+    surfaces: &[BSplineSurface],
+    name: &str,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let max_coordinate = surfaces
+        .iter()
+        .flat_map(|surface| surface.control_points.iter().flatten())
+        .map(|point| point.x.abs().max(point.y.abs()).max(point.z.abs()))
+        .fold(0.0, f64::max);
+    let file_name = path.as_ref().file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    let date = timestamp();
+
+    let global = [
+        hollerith(","),
+        hollerith(";"),
+        hollerith(name),
+        hollerith(&file_name),
+        hollerith("compression-waveguide"),
+        hollerith(env!("CARGO_PKG_VERSION")),
+        "32".to_string(),
+        "38".to_string(),
+        "6".to_string(),
+        "308".to_string(),
+        "15".to_string(),
+        hollerith(name),
+        real(1.0),
+        "2".to_string(), // millimetres
+        hollerith("MM"),
+        "1".to_string(),
+        real(1.0),
+        hollerith(&date),
+        real(1e-6),
+        real(max_coordinate),
+        String::new(),
+        String::new(),
+        "11".to_string(), // IGES 5.3
+        "0".to_string(),
+        hollerith(&date),
+    ];
+
+    // Parameter data lines carry the directory entry they belong to
+    let mut parameter_lines: Vec<(String, usize)> = Vec::new();
+    let mut directory_lines: Vec<String> = Vec::new();
+    for (index, surface) in surfaces.iter().enumerate() {
+        let directory = 2 * index + 1;
+        let lines = wrap(&surface_parameters(surface), 64);
+        let first = parameter_lines.len() + 1;
+        directory_lines.push(format!(
+            "{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+            128, first, 0, 0, 0, 0, 0, 0, "00000000"
+        ));
+        directory_lines.push(format!(
+            "{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+            128, 0, 0, lines.len(), 0, "", "", "SURFACE", index + 1
+        ));
+        parameter_lines.extend(lines.into_iter().map(|line| (line, directory)));
+    }
+
+    let mut start = vec![format!("{} waveguide surfaces", ascii(name))];
+    if let Some(record) = record {
+        start.push(format!("design {}", ascii(&record.to_json())));
+    }
+    // All ASCII, so 72 bytes are 72 columns
+    let start: Vec<String> = start
+        .iter()
+        .flat_map(|text| text.as_bytes().chunks(72).map(|chunk| String::from_utf8_lossy(chunk).into_owned()))
+        .collect();
+    let global = wrap(&global, 72);
+
+    let mut file = BufWriter::new(File::create(path)?);
+    for (index, line) in start.iter().enumerate() {
+        writeln!(file, "{:<72}S{:>7}", line, index + 1)?;
+    }
+    for (index, line) in global.iter().enumerate() {
+        writeln!(file, "{:<72}G{:>7}", line, index + 1)?;
+    }
+    for (index, line) in directory_lines.iter().enumerate() {
+        writeln!(file, "{:<72}D{:>7}", line, index + 1)?;
+    }
+    for (index, (line, directory)) in parameter_lines.iter().enumerate() {
+        writeln!(file, "{:<64} {:>7}P{:>7}", line, directory, index + 1)?;
+    }
+    let terminate = format!(
+        "S{:>7}G{:>7}D{:>7}P{:>7}",
+        start.len(),
+        global.len(),
+        directory_lines.len(),
+        parameter_lines.len()
+    );
+    writeln!(file, "{:<72}T{:>7}", terminate, 1)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry_types::CartesianPoint;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn text_is_escaped_to_ascii() {
+        assert_eq!(ascii("horn"), "horn");
+        assert_eq!(ascii("Cléac'h"), "Cl\\u00e9ac'h");
+        assert_eq!(ascii("𝄞"), "\\ud834\\udd1e");
+        assert_eq!(hollerith("Cléac'h"), "12HCl\\u00e9ac'h");
+    }
+
+    #[test]
+    fn sections_are_in_fixed_columns() {
+        let grid: Vec<Vec<CartesianPoint>> = (0..12)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 12.0;
+                (0..5).map(|j| CartesianPoint::from_cylindrical(10.0 + 10.0 * j as f64, theta, 20.0 * j as f64)).collect()
+            })
+            .collect();
+        let surface = BSplineSurface::interpolate(&grid).unwrap();
+        let path = std::env::temp_dir().join("compression_waveguide_sections.igs");
+        write_iges(&[surface.clone(), surface.clone()], "paroi ancrée", None, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines.iter().all(|line| line.len() == 80));
+
+        // Sections in order, each numbered from 1, counted by the terminate line
+        let sections: Vec<(char, usize)> =
+            lines.iter().map(|line| (line.as_bytes()[72] as char, line[73..].trim().parse().unwrap())).collect();
+        let mut counts = Vec::new();
+        for (section, number) in &sections {
+            match counts.last_mut() {
+                Some((last, count)) if last == section => {
+                    *count += 1;
+                    assert_eq!(number, count);
+                }
+                _ => {
+                    assert_eq!(*number, 1);
+                    counts.push((*section, 1));
+                }
+            }
+        }
+        assert_eq!(counts.iter().map(|(section, _)| *section).collect::<String>(), "SGDPT");
+        let terminate = format!("S{:>7}G{:>7}D{:>7}P{:>7}", counts[0].1, counts[1].1, counts[2].1, counts[3].1);
+        assert!(lines[lines.len() - 1].starts_with(&terminate));
+        assert!(lines[0].starts_with("paroi ancr\\u00e9e"));
+
+        // Every parameter of the second surface, behind its directory entry
+        let parameters: String = sections
+            .iter()
+            .zip(&lines)
+            .filter(|((section, _), line)| *section == 'P' && line[64..72].trim() == "3")
+            .map(|(_, line)| line[..64].trim_end())
+            .collect();
+        assert_eq!(parameters.split(',').count(), surface_parameters(&surface).len());
+        assert!(parameters.starts_with("128,") && parameters.ends_with(';'));
+    }
+}
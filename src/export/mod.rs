@@ -0,0 +1,20 @@
+mod dxf;
+mod gltf;
+mod iges;
+mod obj;
+mod ply;
+mod script;
+mod step;
+mod stl;
+mod three_mf;
+mod zip;
+
+pub use dxf::{profile_sketch, write_dxf, DxfCurve, DxfEntity};
+pub use gltf::write_glb;
+pub use iges::write_iges;
+pub use obj::write_obj;
+pub use ply::{write_ply, PlyAttribute, PlyFormat};
+pub use script::{write_cadquery, write_openscad, ScriptOptions};
+pub use step::write_step;
+pub use stl::{mesh_solids, read_stl, solids_to_mesh, write_mesh_stl, write_stl, write_stl_per_tag, StlFormat, StlSolid};
+pub use three_mf::{write_3mf, ThreeMfObject};
@@ -0,0 +1,124 @@
+use crate::mesh::{Mesh, SurfaceTag};
+use crate::record::DesignRecord;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes the mesh as Wavefront OBJ with vertex normals, one group per surface tag.
+/// `uvs` gives texture coordinates for every vertex, u being periodic with period 1 (θ / 2π): faces
+/// straddling the seam get 1 added to the u of their corners below it. The design `record`
+/// goes in a leading comment.
+pub fn write_obj(
+    mesh: &Mesh,
+    uvs: Option<&[[f64; 2]]>,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    if let Some(uvs) = uvs.filter(|uvs| uvs.len() != mesh.vertices.len()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} texture coordinates for {} vertices", uvs.len(), mesh.vertices.len()),
+        ));
+    }
+    let mut file = BufWriter::new(File::create(path)?);
+    if let Some(record) = record {
+        writeln!(file, "# design {}", record.to_json())?;
+    }
+    for vertex in &mesh.vertices {
+        writeln!(file, "v {} {} {}", vertex.x, vertex.y, vertex.z)?;
+    }
+    for normal in mesh.vertex_normals() {
+        writeln!(file, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+    }
+
+    // Texture coordinates: one per vertex, plus shifted copies for the seam
+    let mut corners: Vec<[usize; 3]> = mesh.triangles.clone();
+    if let Some(uvs) = uvs {
+        for uv in uvs {
+            writeln!(file, "vt {} {}", uv[0], uv[1])?;
+        }
+        let mut shifted: HashMap<usize, usize> = HashMap::new();
+        for (face, corner) in mesh.triangles.iter().zip(corners.iter_mut()) {
+            let u = face.map(|i| uvs[i][0]);
+            let low = u.iter().copied().fold(f64::INFINITY, f64::min);
+            let high = u.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            if high - low > 0.5 {
+                for (k, &i) in face.iter().enumerate() {
+                    if u[k] < 0.5 {
+                        corner[k] = match shifted.get(&i) {
+                            Some(&index) => index,
+                            None => {
+                                let index = uvs.len() + shifted.len();
+                                writeln!(file, "vt {} {}", uvs[i][0] + 1.0, uvs[i][1])?;
+                                shifted.insert(i, index);
+                                index
+                            }
+                        };
+                    }
+                }
+            }
+        }
+    }
+
+    for tag in SurfaceTag::ALL {
+        let faces: Vec<usize> = (0..mesh.triangles.len()).filter(|&f| mesh.tags[f] == tag).collect();
+        if faces.is_empty() {
+            continue;
+        }
+        writeln!(file, "g {}", tag.name())?;
+        for f in faces {
+            let face = mesh.triangles[f];
+            write!(file, "f")?;
+            for k in 0..3 {
+                // OBJ indices start at 1
+                match uvs {
+                    Some(_) => write!(file, " {0}/{1}/{0}", face[k] + 1, corners[f][k] + 1)?,
+                    None => write!(file, " {0}//{0}", face[k] + 1)?,
+                }
+            }
+            writeln!(file)?;
+        }
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry_types::CartesianPoint;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn seam_faces_wrap_their_texture() {
+        // Cylinder of 8 angles and 3 points, u = θ / 2π
+        let grid: Vec<Vec<CartesianPoint>> = (0..8)
+            .map(|i| (0..3).map(|j| CartesianPoint::from_cylindrical(10.0, 2.0 * PI * i as f64 / 8.0, 10.0 * j as f64)).collect())
+            .collect();
+        let mesh = Mesh::from_grid(&grid, true, SurfaceTag::Wall);
+        let uvs: Vec<[f64; 2]> = (0..8).flat_map(|i| (0..3).map(move |j| [i as f64 / 8.0, j as f64 / 2.0])).collect();
+        let path = std::env::temp_dir().join("compression_waveguide_seam.obj");
+        assert!(write_obj(&mesh, Some(&uvs[1..]), None, &path).is_err());
+        write_obj(&mesh, Some(&uvs), None, &path).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let texture: Vec<f64> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("vt "))
+            .map(|line| line.split(' ').next().unwrap().parse().unwrap())
+            .collect();
+        // The θ = 0 column again at u = 1
+        assert_eq!(texture.len(), 24 + 3);
+        assert!(texture[24..].iter().all(|&u| u == 1.0));
+        let faces: Vec<Vec<f64>> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("f "))
+            .map(|line| line.split(' ').map(|corner| texture[corner.split('/').nth(1).unwrap().parse::<usize>().unwrap() - 1]).collect())
+            .collect();
+        assert_eq!(faces.len(), mesh.triangles.len());
+        for u in faces {
+            let spread = u.iter().copied().fold(0.0, f64::max) - u.iter().copied().fold(1.0, f64::min);
+            assert!(spread <= 1.0 / 8.0 + 1e-12);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::mesh::{Mesh, SurfaceTag};
+use crate::record::DesignRecord;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    Binary, // little endian
+}
+
+/// Named scalar given at every vertex, written as a vertex property (e.g. wall angle, curvature)
+#[derive(Debug, Clone)]
+pub struct PlyAttribute {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+/// Writes the mesh as PLY: vertex positions, unit normals and `attributes`, and faces with the
+/// index of their tag in `SurfaceTag::ALL`. The tags and the design `record` are listed in the
+/// header comments.
+pub fn write_ply(
+    mesh: &Mesh,
+    attributes: &[PlyAttribute],
+    format: PlyFormat,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    if let Some(attribute) = attributes.iter().find(|attribute| attribute.values.len() != mesh.vertices.len()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "attribute {} has {} values for {} vertices",
+                attribute.name,
+                attribute.values.len(),
+                mesh.vertices.len()
+            ),
+        ));
+    }
+    let normals = mesh.vertex_normals();
+    let tag_index = |tag: SurfaceTag| SurfaceTag::ALL.iter().position(|&other| other == tag).unwrap() as u8;
+
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "ply")?;
+    match format {
+        PlyFormat::Ascii => writeln!(file, "format ascii 1.0")?,
+        PlyFormat::Binary => writeln!(file, "format binary_little_endian 1.0")?,
+    }
+    writeln!(file, "comment units mm")?;
+    if let Some(record) = record {
+        writeln!(file, "comment design {}", record.to_json())?;
+    }
+    for (index, tag) in SurfaceTag::ALL.iter().enumerate() {
+        writeln!(file, "comment tag {} {}", index, tag.name())?;
+    }
+    writeln!(file, "element vertex {}", mesh.vertices.len())?;
+    for name in ["x", "y", "z", "nx", "ny", "nz"] {
+        writeln!(file, "property double {}", name)?;
+    }
+    for attribute in attributes {
+        writeln!(file, "property double {}", attribute.name)?;
+    }
+    writeln!(file, "element face {}", mesh.triangles.len())?;
+    writeln!(file, "property list uchar int vertex_indices")?;
+    writeln!(file, "property uchar tag")?;
+    writeln!(file, "end_header")?;
+
+    for (i, (vertex, normal)) in mesh.vertices.iter().zip(&normals).enumerate() {
+        let values = [vertex.x, vertex.y, vertex.z, normal.x, normal.y, normal.z]
+            .into_iter()
+            .chain(attributes.iter().map(|attribute| attribute.values[i]));
+        match format {
+            PlyFormat::Ascii => {
+                let values: Vec<String> = values.map(|value| value.to_string()).collect();
+                writeln!(file, "{}", values.join(" "))?;
+            }
+            PlyFormat::Binary => {
+                for value in values {
+                    file.write_all(&value.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    for (face, &tag) in mesh.triangles.iter().zip(&mesh.tags) {
+        match format {
+            PlyFormat::Ascii => writeln!(file, "3 {} {} {} {}", face[0], face[1], face[2], tag_index(tag))?,
+            PlyFormat::Binary => {
+                file.write_all(&[3])?;
+                for &index in face {
+                    file.write_all(&(index as i32).to_le_bytes())?;
+                }
+                file.write_all(&[tag_index(tag)])?;
+            }
+        }
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry_types::CartesianPoint;
+
+    /// Header lines and the body following `end_header`
+    fn split_header(bytes: &[u8]) -> (Vec<String>, &[u8]) {
+        let end = b"end_header\n";
+        let at = bytes.windows(end.len()).position(|window| window == end).unwrap() + end.len();
+        let header = String::from_utf8(bytes[..at].to_vec()).unwrap();
+        (header.lines().map(str::to_string).collect(), &bytes[at..])
+    }
+
+    #[test]
+    fn attributes_round_trip() {
+        let square = [
+            CartesianPoint { x: 0.0, y: 0.0, z: 0.0 },
+            CartesianPoint { x: 1.0, y: 0.0, z: 0.0 },
+            CartesianPoint { x: 1.0, y: 1.0, z: 0.0 },
+            CartesianPoint { x: 0.0, y: 1.0, z: 0.0 },
+        ];
+        let mut mesh = Mesh::from_triangles(&[[square[0], square[1], square[2]]], SurfaceTag::Wall);
+        let last = mesh.add_vertex(square[3]);
+        mesh.add_triangle([0, 2, last], SurfaceTag::Source);
+        let angle = PlyAttribute { name: "wall_angle".to_string(), values: vec![0.5, -1.25, 3.0e-7, 42.0] };
+        let path = std::env::temp_dir().join("compression_waveguide_attributes.ply");
+        let short = PlyAttribute { values: vec![0.0; 3], ..angle.clone() };
+        assert!(write_ply(&mesh, &[short], PlyFormat::Ascii, None, &path).is_err());
+
+        for format in [PlyFormat::Ascii, PlyFormat::Binary] {
+            write_ply(&mesh, std::slice::from_ref(&angle), format, None, &path).unwrap();
+            let bytes = std::fs::read(&path).unwrap();
+            let (header, body) = split_header(&bytes);
+            assert_eq!(header[0], "ply");
+            assert!(header.contains(&"element vertex 4".to_string()) && header.contains(&"element face 2".to_string()));
+            let properties: Vec<&str> = header.iter().filter_map(|line| line.strip_prefix("property double ")).collect();
+            assert_eq!(properties, ["x", "y", "z", "nx", "ny", "nz", "wall_angle"]);
+            let source = SurfaceTag::ALL.iter().position(|&tag| tag == SurfaceTag::Source).unwrap();
+            assert!(header.contains(&format!("comment tag {} source", source)));
+
+            // Seven doubles per vertex, then the faces with their tags
+            let (values, faces): (Vec<f64>, Vec<(Vec<i32>, u8)>) = match format {
+                PlyFormat::Ascii => {
+                    let lines: Vec<&str> = std::str::from_utf8(body).unwrap().lines().collect();
+                    let values = lines[..4].iter().flat_map(|line| line.split(' ').map(|value| value.parse().unwrap())).collect();
+                    let faces = lines[4..]
+                        .iter()
+                        .map(|line| {
+                            let numbers: Vec<i32> = line.split(' ').map(|value| value.parse().unwrap()).collect();
+                            (numbers[1..4].to_vec(), numbers[4] as u8)
+                        })
+                        .collect();
+                    (values, faces)
+                }
+                PlyFormat::Binary => {
+                    let values =
+                        body[..4 * 7 * 8].chunks(8).map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap())).collect();
+                    let faces = body[4 * 7 * 8..]
+                        .chunks(14)
+                        .map(|face| {
+                            assert_eq!(face[0], 3);
+                            let indices = face[1..13].chunks(4).map(|bytes| i32::from_le_bytes(bytes.try_into().unwrap()));
+                            (indices.collect(), face[13])
+                        })
+                        .collect();
+                    (values, faces)
+                }
+            };
+            assert_eq!(values.len(), 4 * 7);
+            for (i, vertex) in values.chunks(7).enumerate() {
+                assert_eq!(vertex[..3], [square[i].x, square[i].y, square[i].z]);
+                assert_eq!(vertex[5], 1.0);
+                assert_eq!(vertex[6], angle.values[i]);
+            }
+            assert_eq!(faces, [(vec![0, 1, 2], 0), (vec![0, 2, 3], source as u8)]);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::geometry_types::ProfilePoint;
+use crate::record::DesignRecord;
+use crate::throat::Flange;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Construction parameters written as editable variables at the top of exported scripts
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptOptions {
+    pub length: f64, // waveguide length, where the tilted axis ends
+    pub tilt: f64,
+    pub thickness: f64, // wall, offset along the profile normals away from the axis
+    pub flange: Option<Flange>, // plate in the throat plane, behind it
+}
+
+/// Profiles as nested lists of (z, r) per angle, `angle` converting θ for the target language
+fn profile_data(profiles: &[Vec<ProfilePoint>], angle: impl Fn(f64) -> f64, open: char, close: char) -> String {
+    profiles
+        .iter()
+        .map(|profile| {
+            let points: Vec<String> = profile
+                .iter()
+                .map(|point| format!("{open}{}, {}{close}", point.z, point.r))
+                .collect();
+            format!("    {open}{}, [{}]{close}", angle(profile[0].theta), points.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(",\n")
+}
+
+/// Writes an OpenSCAD script rebuilding the solid from the profiles: each angle's cross-section
+/// (inner wall, offset outer wall and the rims joining them) is placed on the tilted axis and
+/// the sections are joined into a polyhedron, with an optional flange around the throat.
+/// The plain normal offset suits profiles that do not roll back into a lip. The design `record`
+/// goes in the header comment.
+pub fn write_openscad(
+    profiles: &[Vec<ProfilePoint>],
+    options: &ScriptOptions,
+    name: &str,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let design = record.map(|record| format!("// design {}\n", record.to_json())).unwrap_or_default();
+    let flange = options.flange.unwrap_or(Flange { outer_radius: 0.0, thickness: 0.0 });
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(
+        file,
+        r#"// {name}: generated by compression-waveguide {version}
+{design}// profiles: [theta (degrees), [[z, r], ...]] in mm, one per angle around the axis
+
+length = {length};
+tilt = {tilt}; // degrees, bends the axis towards -y
+thickness = {thickness};
+flange_radius = {flange_radius}; // 0 for no flange
+flange_thickness = {flange_thickness};
+
+profiles = [
+{profiles}
+];
+
+// Unit tangent at point i from its neighbours, widened past repeated points, along the axis
+// when all the points coincide
+function tangent(p, i, w = 1) =
+    let (d = p[min(i + w, len(p) - 1)] - p[max(i - w, 0)])
+    norm(d) > 0 ? d / norm(d) : w >= len(p) ? [1, 0] : tangent(p, i, w + 1);
+
+// Offset along the profile normals, away from the axis
+function offset_profile(p, t) = [for (i = [0 : len(p) - 1])
+    let (d = tangent(p, i))
+    [p[i][0] - t * d[1], p[i][1] + t * d[0]]];
+
+// Closed cross-section: outer wall from the throat to the mouth, inner wall back
+function section(p) = concat(offset_profile(p, thickness), [for (i = [len(p) - 1 : -1 : 0]) p[i]]);
+
+// Cylindrical placement on the axis bent by `tilt` up to z = length
+function place(theta, q) =
+    let (z = q[0], x = q[1] * cos(theta), y = q[1] * sin(theta))
+    tilt == 0 ? [x, y, z] :
+    let (
+        bend = length / (tilt * PI / 180),
+        phi = tilt * min(max(z / length, 0), 1),
+        axis = z < 0 ? [0, z]
+            : z <= length ? [-bend * (1 - cos(phi)), bend * sin(phi)]
+            : [-bend * (1 - cos(tilt)) - (z - length) * sin(tilt), bend * sin(tilt) + (z - length) * cos(tilt)]
+    )
+    [x, axis[0] + y * cos(phi), axis[1] + y * sin(phi)];
+
+count = len(profiles);
+loop = 2 * len(profiles[0][1]);
+points = [for (s = profiles) for (q = section(s[1])) place(s[0], q)];
+
+// Triangles between neighbouring sections, clockwise seen from outside
+faces = [for (i = [0 : count - 1], k = [0 : loop - 1])
+    let (
+        a = i * loop + k,
+        b = i * loop + (k + 1) % loop,
+        c = (i + 1) % count * loop + k,
+        d = (i + 1) % count * loop + (k + 1) % loop
+    )
+    each [[a, b, c], [b, d, c]]];
+
+polyhedron(points, faces, convexity = 10);
+
+if (flange_radius > 0)
+    translate([0, 0, -flange_thickness]) linear_extrude(flange_thickness) difference() {{
+        circle(r = flange_radius, $fn = 128);
+        polygon([for (s = profiles) let (p = place(s[0], s[1][0])) [p[0], p[1]]]);
+    }}
+"#,
+        name = name,
+        version = env!("CARGO_PKG_VERSION"),
+        design = design,
+        length = options.length,
+        tilt = options.tilt.to_degrees(),
+        thickness = options.thickness,
+        flange_radius = flange.outer_radius,
+        flange_thickness = flange.thickness,
+        profiles = profile_data(profiles, f64::to_degrees, '[', ']'),
+    )?;
+    file.flush()
+}
+
+/// Writes a CadQuery script building the same solid as `write_openscad` from planar triangles,
+/// shown in CQ-editor or exported to `<name>.step` when run on its own
+pub fn write_cadquery(
+    profiles: &[Vec<ProfilePoint>],
+    options: &ScriptOptions,
+    name: &str,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let design = record.map(|record| format!("# design {}\n", record.to_json())).unwrap_or_default();
+    let flange = options.flange.unwrap_or(Flange { outer_radius: 0.0, thickness: 0.0 });
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(
+        file,
+        r#"# {name}: generated by compression-waveguide {version}
+{design}# profiles: (theta (radians), [(z, r), ...]) in mm, one per angle around the axis
+import math
+
+import cadquery as cq
+
+length = {length}
+tilt = math.radians({tilt})  # bends the axis towards -y
+thickness = {thickness}
+flange_radius = {flange_radius}  # 0 for no flange
+flange_thickness = {flange_thickness}
+
+profiles = [
+{profiles}
+]
+
+
+def tangent(p, i):
+    """Unit tangent at point i from its neighbours, widened past repeated points"""
+    for w in range(1, len(p)):
+        a, b = p[max(i - w, 0)], p[min(i + w, len(p) - 1)]
+        dz, dr = b[0] - a[0], b[1] - a[1]
+        norm = math.hypot(dz, dr)
+        if norm > 0:
+            return dz / norm, dr / norm
+    return 1.0, 0.0  # all the points coincide
+
+
+def offset_profile(p, t):
+    """Offset along the profile normals, away from the axis"""
+    points = []
+    for i in range(len(p)):
+        dz, dr = tangent(p, i)
+        points.append((p[i][0] - t * dr, p[i][1] + t * dz))
+    return points
+
+
+def section(p):
+    """Closed cross-section: outer wall from the throat to the mouth, inner wall back"""
+    return offset_profile(p, thickness) + p[::-1]
+
+
+def place(theta, q):
+    """Cylindrical placement on the axis bent by `tilt` up to z = length"""
+    z, r = q
+    x, y = r * math.cos(theta), r * math.sin(theta)
+    if tilt == 0:
+        return cq.Vector(x, y, z)
+    bend = length / tilt
+    phi = tilt * min(max(z / length, 0.0), 1.0)
+    if z < 0:
+        axis = (0.0, z)
+    elif z <= length:
+        axis = (-bend * (1 - math.cos(phi)), bend * math.sin(phi))
+    else:
+        axis = (
+            -bend * (1 - math.cos(tilt)) - (z - length) * math.sin(tilt),
+            bend * math.sin(tilt) + (z - length) * math.cos(tilt),
+        )
+    return cq.Vector(x, axis[0] + y * math.cos(phi), axis[1] + y * math.sin(phi))
+
+
+sections = [[place(theta, q) for q in section(p)] for theta, p in profiles]
+faces = []
+for i, current in enumerate(sections):
+    following = sections[(i + 1) % len(sections)]
+    for k in range(len(current)):
+        a, b = current[k], current[(k + 1) % len(current)]
+        c, d = following[k], following[(k + 1) % len(current)]
+        for triangle in ((a, c, b), (b, c, d)):  # counter-clockwise seen from outside
+            faces.append(cq.Face.makeFromWires(cq.Wire.makePolygon(triangle, close=True)))
+result = cq.Workplane("XY").add(cq.Solid.makeSolid(cq.Shell.makeShell(faces)))
+
+if flange_radius > 0:
+    throat = [place(theta, p[0]) for theta, p in profiles]
+    flange = (
+        cq.Workplane("XY")
+        .workplane(offset=-flange_thickness)
+        .circle(flange_radius)
+        .polyline([(v.x, v.y) for v in throat])
+        .close()
+        .extrude(flange_thickness)
+    )
+    result = result.union(flange)
+
+if "show_object" in globals():
+    show_object(result)
+else:
+    cq.exporters.export(result, "{name}.step")
+"#,
+        name = name,
+        version = env!("CARGO_PKG_VERSION"),
+        design = design,
+        length = options.length,
+        tilt = options.tilt.to_degrees(),
+        thickness = options.thickness,
+        flange_radius = flange.outer_radius,
+        flange_thickness = flange.thickness,
+        profiles = profile_data(profiles, |theta| theta, '(', ')'),
+    )?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// The `profiles` table of a script, brackets and parentheses alike read as JSON arrays
+    fn profile_table(path: &Path) -> Vec<(f64, Vec<(f64, f64)>)> {
+        let text = std::fs::read_to_string(path).unwrap();
+        let (_, table) = text.split_once("\nprofiles = [\n").unwrap();
+        let (table, _) = table.split_once("\n]").unwrap();
+        serde_json::from_str(&format!("[{}]", table.replace('(', "[").replace(')', "]"))).unwrap()
+    }
+
+    #[test]
+    fn scripts_carry_every_profile_point() {
+        // A lip repeating the mouth point, as left by a zero-length termination
+        let profiles: Vec<Vec<ProfilePoint>> = (0..6)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 6.0 - PI;
+                let mut profile: Vec<ProfilePoint> =
+                    (0..5).map(|j| ProfilePoint { z: 12.5 * j as f64 / 3.0, r: 10.0 + 0.1 * (i * j) as f64, theta }).collect();
+                profile.push(profile[4]);
+                profile
+            })
+            .collect();
+        let options = ScriptOptions { length: 50.0 / 3.0, tilt: 0.1, thickness: 3.0, flange: None };
+        let directory = std::env::temp_dir();
+        let scad = directory.join("compression_waveguide_points.scad");
+        let python = directory.join("compression_waveguide_points.py");
+        write_openscad(&profiles, &options, "horn", None, &scad).unwrap();
+        write_cadquery(&profiles, &options, "horn", None, &python).unwrap();
+
+        for (path, angle) in [(&scad, f64::to_degrees as fn(f64) -> f64), (&python, |theta| theta)] {
+            let table = profile_table(path);
+            assert_eq!(table.len(), profiles.len());
+            for ((theta, points), profile) in table.iter().zip(&profiles) {
+                // serde_json parses floats to within an ulp or so
+                let close = |a: f64, b: f64| (a - b).abs() <= 1e-12 * b.abs().max(1.0);
+                assert!(close(*theta, angle(profile[0].theta)));
+                assert_eq!(points.len(), profile.len());
+                assert!(points.iter().zip(profile).all(|(&(z, r), point)| close(z, point.z) && close(r, point.r)));
+            }
+        }
+    }
+}
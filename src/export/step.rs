@@ -0,0 +1,278 @@
+use crate::geometry_types::CartesianPoint;
+use crate::nurbs::{BSplineCurve, BSplineSurface};
+use crate::record::DesignRecord;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// STEP (ISO 10303-21, AP214) file being assembled, one entity per line
+struct StepWriter {
+    entities: Vec<String>,
+}
+
+impl StepWriter {
+    /// Adds an entity and returns its instance number
+    fn add(&mut self, entity: String) -> usize {
+        self.entities.push(entity);
+        self.entities.len()
+    }
+
+    fn point(&mut self, point: &CartesianPoint) -> usize {
+        self.add(format!(
+            "CARTESIAN_POINT('',({},{},{}))",
+            real(point.x),
+            real(point.y),
+            real(point.z)
+        ))
+    }
+
+    fn direction(&mut self, x: f64, y: f64, z: f64) -> usize {
+        self.add(format!("DIRECTION('',({},{},{}))", real(x), real(y), real(z)))
+    }
+
+    fn curve(&mut self, curve: &BSplineCurve) -> usize {
+        let points: Vec<String> = curve
+            .control_points
+            .iter()
+            .map(|point| format!("#{}", self.point(point)))
+            .collect();
+        let (multiplicities, knots) = knot_list(&curve.knots);
+        self.add(format!(
+            "B_SPLINE_CURVE_WITH_KNOTS('',{},({}),.UNSPECIFIED.,{},.F.,({}),({}),.UNSPECIFIED.)",
+            curve.degree,
+            points.join(","),
+            logical(curve.closed),
+            multiplicities,
+            knots
+        ))
+    }
+
+    fn surface(&mut self, surface: &BSplineSurface) -> usize {
+        let rows: Vec<String> = surface
+            .control_points
+            .iter()
+            .map(|row| {
+                let points: Vec<String> = row.iter().map(|point| format!("#{}", self.point(point))).collect();
+                format!("({})", points.join(","))
+            })
+            .collect();
+        let (multiplicities_u, knots_u) = knot_list(&surface.knots_u);
+        let (multiplicities_v, knots_v) = knot_list(&surface.knots_v);
+        self.add(format!(
+            "B_SPLINE_SURFACE_WITH_KNOTS('',{},{},({}),.UNSPECIFIED.,.T.,.F.,.F.,({}),({}),({}),({}),.UNSPECIFIED.)",
+            surface.degree_u,
+            surface.degree_v,
+            rows.join(","),
+            multiplicities_u,
+            multiplicities_v,
+            knots_u,
+            knots_v
+        ))
+    }
+}
+
+/// STEP real, which always needs a decimal point
+fn real(value: f64) -> String {
+    format!("{:.12E}", value)
+}
+
+/// Quoted STEP string: apostrophes and backslashes doubled, characters outside printable
+/// ASCII written as \X2\ UCS-2 hex (surrogate pairs for characters beyond the BMP)
+fn string(text: &str) -> String {
+    let mut quoted = String::from("'");
+    for character in text.chars() {
+        match character {
+            '\'' => quoted.push_str("''"),
+            '\\' => quoted.push_str("\\\\"),
+            ' '..='~' => quoted.push(character),
+            _ => {
+                let mut units = [0; 2];
+                quoted.push_str("\\X2\\");
+                for unit in character.encode_utf16(&mut units) {
+                    quoted.push_str(&format!("{:04X}", unit));
+                }
+                quoted.push_str("\\X0\\");
+            }
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+fn logical(value: bool) -> &'static str {
+    if value {
+        ".T."
+    } else {
+        ".F."
+    }
+}
+
+/// Distinct knot values and their multiplicities, as STEP lists
+fn knot_list(knots: &[f64]) -> (String, String) {
+    let mut distinct: Vec<(f64, usize)> = Vec::new();
+    for &knot in knots {
+        match distinct.last_mut() {
+            Some((value, count)) if *value == knot => *count += 1,
+            _ => distinct.push((knot, 1)),
+        }
+    }
+    let multiplicities: Vec<String> = distinct.iter().map(|(_, count)| count.to_string()).collect();
+    let values: Vec<String> = distinct.iter().map(|&(value, _)| real(value)).collect();
+    (multiplicities.join(","), values.join(","))
+}
+
+/// Writes B-spline faces, as fitted by `fit_surfaces`, to an AP214 STEP file in millimetres.
+/// Each face ends (v = 1) where the next one starts (v = 0). With `closed` the chain wraps
+/// around into a closed shell written as a solid, otherwise into an open shell written as a
+/// surface model. Face normals point away from the axis side of the first face. The design
+/// `record` goes in the file description. Fails on an empty `faces`.
+pub fn write_step(
+    faces: &[BSplineSurface],
+    closed: bool,
+    name: &str,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    if faces.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "no face to write"));
+    }
+    let name = &string(name);
+    let mut step = StepWriter { entities: Vec::new() };
+
+    // Product structure and millimetre context
+    let application = step.add("APPLICATION_CONTEXT('automotive design')".to_string());
+    step.add(format!(
+        "APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#{})",
+        application
+    ));
+    let product_context = step.add(format!("PRODUCT_CONTEXT('',#{},'mechanical')", application));
+    let product = step.add(format!("PRODUCT({0},{0},'',(#{1}))", name, product_context));
+    step.add(format!("PRODUCT_RELATED_PRODUCT_CATEGORY('part',$,(#{}))", product));
+    let formation = step.add(format!("PRODUCT_DEFINITION_FORMATION('','',#{})", product));
+    let definition_context = step.add(format!("PRODUCT_DEFINITION_CONTEXT('part definition',#{},'design')", application));
+    let definition = step.add(format!("PRODUCT_DEFINITION('design','',#{},#{})", formation, definition_context));
+    let definition_shape = step.add(format!("PRODUCT_DEFINITION_SHAPE('','',#{})", definition));
+    let length_unit = step.add("(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))".to_string());
+    let angle_unit = step.add("(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))".to_string());
+    let solid_angle_unit = step.add("(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())".to_string());
+    let uncertainty = step.add(format!(
+        "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(1.E-06),#{},'distance_accuracy_value','confusion accuracy')",
+        length_unit
+    ));
+    let context = step.add(format!(
+        "(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{}))GLOBAL_UNIT_ASSIGNED_CONTEXT((#{},#{},#{}))REPRESENTATION_CONTEXT('',''))",
+        uncertainty, length_unit, angle_unit, solid_angle_unit
+    ));
+    let origin = step.point(&CartesianPoint { x: 0.0, y: 0.0, z: 0.0 });
+    let axis = step.direction(0.0, 0.0, 1.0);
+    let reference = step.direction(1.0, 0.0, 0.0);
+    let placement = step.add(format!("AXIS2_PLACEMENT_3D('',#{},#{},#{})", origin, axis, reference));
+
+    // Boundary rings shared by consecutive faces, each a closed edge on one vertex
+    let ring_count = if closed { faces.len() } else { faces.len() + 1 };
+    let rings: Vec<(usize, usize)> = (0..ring_count)
+        .map(|r| {
+            let curve = if r < faces.len() {
+                faces[r].boundary(false)
+            } else {
+                faces[faces.len() - 1].boundary(true)
+            };
+            let start = curve.evaluate(curve.domain().0);
+            let point = step.point(&start);
+            let vertex = step.add(format!("VERTEX_POINT('',#{})", point));
+            let curve = step.curve(&curve);
+            let edge = step.add(format!("EDGE_CURVE('',#{0},#{0},#{1},.T.)", vertex, curve));
+            (vertex, edge)
+        })
+        .collect();
+
+    // Normals away from the axis side of the first face, i.e. into the horn and out of the shell
+    let same_sense = {
+        let surface = &faces[0];
+        let (u, v, h) = (0.5, 0.5, 1e-6);
+        let point = surface.evaluate(u, v);
+        let su = surface.evaluate(u + h, v) - surface.evaluate(u - h, v);
+        let sv = surface.evaluate(u, v + h) - surface.evaluate(u, v - h);
+        su.cross(&sv).dot(&CartesianPoint { z: 0.0, ..point }) < 0.0
+    };
+
+    let face_ids: Vec<usize> = faces
+        .iter()
+        .enumerate()
+        .map(|(f, surface)| {
+            let (bottom, top) = (rings[f], rings[(f + 1) % ring_count]);
+            let seam_curve = step.curve(&surface.seam());
+            let seam = step.add(format!("EDGE_CURVE('',#{},#{},#{},.T.)", bottom.0, top.0, seam_curve));
+
+            // Counter-clockwise around the (u, v) domain, the seam walked up then down
+            let edges: Vec<String> = [(bottom.1, true), (seam, true), (top.1, false), (seam, false)]
+                .into_iter()
+                .map(|(edge, forward)| {
+                    format!("#{}", step.add(format!("ORIENTED_EDGE('',*,*,#{},{})", edge, logical(forward))))
+                })
+                .collect();
+            let edge_loop = step.add(format!("EDGE_LOOP('',({}))", edges.join(",")));
+            let bound = step.add(format!("FACE_OUTER_BOUND('',#{},.T.)", edge_loop));
+            let surface = step.surface(surface);
+            step.add(format!("ADVANCED_FACE('',(#{}),#{},{})", bound, surface, logical(same_sense)))
+        })
+        .collect();
+    let face_list: Vec<String> = face_ids.iter().map(|id| format!("#{}", id)).collect();
+
+    let representation = if closed {
+        let shell = step.add(format!("CLOSED_SHELL('',({}))", face_list.join(",")));
+        let brep = step.add(format!("MANIFOLD_SOLID_BREP({},#{})", name, shell));
+        step.add(format!(
+            "ADVANCED_BREP_SHAPE_REPRESENTATION({},(#{},#{}),#{})",
+            name, placement, brep, context
+        ))
+    } else {
+        let shell = step.add(format!("OPEN_SHELL('',({}))", face_list.join(",")));
+        let model = step.add(format!("SHELL_BASED_SURFACE_MODEL({},(#{}))", name, shell));
+        step.add(format!(
+            "MANIFOLD_SURFACE_SHAPE_REPRESENTATION({},(#{},#{}),#{})",
+            name, placement, model, context
+        ))
+    };
+    step.add(format!("SHAPE_DEFINITION_REPRESENTATION(#{},#{})", definition_shape, representation));
+
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "ISO-10303-21;")?;
+    writeln!(file, "HEADER;")?;
+    match record {
+        Some(record) => {
+            let design = string(&format!("design {}", record.to_json()));
+            writeln!(file, "FILE_DESCRIPTION(({},{}),'2;1');", name, design)?
+        }
+        None => writeln!(file, "FILE_DESCRIPTION(({}),'2;1');", name)?,
+    }
+    writeln!(file, "FILE_NAME({},'',(''),(''),'compression-waveguide','','');", name)?;
+    writeln!(file, "FILE_SCHEMA(('AUTOMOTIVE_DESIGN {{ 1 0 10303 214 1 1 1 1 }}'));")?;
+    writeln!(file, "ENDSEC;")?;
+    writeln!(file, "DATA;")?;
+    for (index, entity) in step.entities.iter().enumerate() {
+        writeln!(file, "#{}={};", index + 1, entity)?;
+    }
+    writeln!(file, "ENDSEC;")?;
+    writeln!(file, "END-ISO-10303-21;")?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(string("horn"), "'horn'");
+        assert_eq!(string("tom's \\ horn"), "'tom''s \\\\ horn'");
+        assert_eq!(string("Cléac'h"), "'Cl\\X2\\00E9\\X0\\ac''h'");
+    }
+
+    #[test]
+    fn empty_faces_are_rejected() {
+        let path = std::env::temp_dir().join("compression_waveguide_empty.step");
+        let error = write_step(&[], true, "empty", None, &path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
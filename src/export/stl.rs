@@ -0,0 +1,355 @@
+use crate::geometry_types::CartesianPoint;
+use crate::mesh::{Mesh, SurfaceTag};
+use crate::record::{sidecar_path, write_record, DesignRecord};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Start of the binary header, before the solid names. Not "solid", which would make readers
+/// sniffing the first bytes take the file for ASCII.
+const BINARY_HEADER: &str = "compression-waveguide";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,  // full precision, one `solid` block per named solid
+    Binary, // f32 coordinates, solid names in the header and solid indices in the attribute words
+}
+
+/// Named group of triangles, one `solid` block of an ASCII STL
+#[derive(Debug, Clone)]
+pub struct StlSolid {
+    pub name: String,
+    pub triangles: Vec<[CartesianPoint; 3]>,
+}
+
+fn unit_normal(triangle: &[CartesianPoint; 3]) -> CartesianPoint {
+    let normal = (triangle[1] - triangle[0]).cross(&(triangle[2] - triangle[0]));
+    if normal.norm() > 0.0 {
+        normal * (1.0 / normal.norm())
+    } else {
+        normal
+    }
+}
+
+/// Longest prefix of `text` of at most `limit` bytes ending on a character boundary
+fn truncate(text: &str, limit: usize) -> &str {
+    let end = (0..=limit.min(text.len())).rev().find(|&end| text.is_char_boundary(end)).unwrap_or(0);
+    &text[..end]
+}
+
+/// Writes the solids to `path`. The design `record` follows each solid name in ASCII files; a
+/// binary header holds the generator and as many whole solid names as fit in its 80 bytes,
+/// then only has room for the record summary, after a NUL, the record going to the JSON
+/// sidecar. Each binary triangle keeps the index of its solid in its attribute word.
+/// Fails on names with whitespace, which readers take for the end of the name, and on more
+/// binary solids than attribute words can number.
+pub fn write_stl(
+    solids: &[StlSolid],
+    format: StlFormat,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    if let Some(solid) = solids.iter().find(|solid| solid.name.contains(char::is_whitespace)) {
+        return Err(invalid(format!("STL solid name {:?} contains whitespace", solid.name)));
+    }
+    if format == StlFormat::Binary && solids.len() > u16::MAX as usize + 1 {
+        return Err(invalid(format!("{} solids do not fit the binary STL attribute word", solids.len())));
+    }
+
+    let path = path.as_ref();
+    let mut file = BufWriter::new(File::create(path)?);
+    match format {
+        StlFormat::Ascii => {
+            let record = record.map(|record| format!(" {}", record.to_json())).unwrap_or_default();
+            for solid in solids {
+                writeln!(file, "solid {}{}", solid.name, record)?;
+                for triangle in &solid.triangles {
+                    let n = unit_normal(triangle);
+                    writeln!(file, "  facet normal {:e} {:e} {:e}", n.x, n.y, n.z)?;
+                    writeln!(file, "    outer loop")?;
+                    for v in triangle {
+                        writeln!(file, "      vertex {:e} {:e} {:e}", v.x, v.y, v.z)?;
+                    }
+                    writeln!(file, "    endloop")?;
+                    writeln!(file, "  endfacet")?;
+                }
+                writeln!(file, "endsolid {}", solid.name)?;
+            }
+        }
+        StlFormat::Binary => {
+            let mut header = [0u8; 80];
+            let mut text = BINARY_HEADER.to_string();
+            for solid in solids {
+                if text.len() + 1 + solid.name.len() > header.len() {
+                    break;
+                }
+                text.push(' ');
+                text.push_str(&solid.name);
+            }
+            if let Some(record) = record {
+                text.push('\0');
+                text.push_str(&record.summary());
+                write_record(record, sidecar_path(path))?;
+            }
+            let text = truncate(&text, header.len());
+            header[..text.len()].copy_from_slice(text.as_bytes());
+            file.write_all(&header)?;
+
+            let count: usize = solids.iter().map(|solid| solid.triangles.len()).sum();
+            file.write_all(&(count as u32).to_le_bytes())?;
+            for (index, solid) in solids.iter().enumerate() {
+                for triangle in &solid.triangles {
+                    for v in std::iter::once(unit_normal(triangle)).chain(triangle.iter().copied()) {
+                        for coordinate in [v.x, v.y, v.z] {
+                            file.write_all(&(coordinate as f32).to_le_bytes())?;
+                        }
+                    }
+                    file.write_all(&(index as u16).to_le_bytes())?;
+                }
+            }
+        }
+    }
+    file.flush()
+}
+
+/// One solid per surface tag present in the mesh, named after the tag
+pub fn mesh_solids(mesh: &Mesh) -> Vec<StlSolid> {
+    SurfaceTag::ALL
+        .iter()
+        .map(|&tag| StlSolid {
+            name: tag.name().to_string(),
+            triangles: mesh
+                .triangles
+                .iter()
+                .zip(&mesh.tags)
+                .filter(|(_, &t)| t == tag)
+                .map(|(face, _)| face.map(|i| mesh.vertices[i]))
+                .collect(),
+        })
+        .filter(|solid| !solid.triangles.is_empty())
+        .collect()
+}
+
+/// Writes the tagged surfaces of the mesh as the solids of a single file
+pub fn write_mesh_stl(
+    mesh: &Mesh,
+    format: StlFormat,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    write_stl(&mesh_solids(mesh), format, record, path)
+}
+
+/// Writes each tagged surface to its own file next to `path`, named `<stem>_<tag>.stl`.
+/// Returns the paths written.
+pub fn write_stl_per_tag(
+    mesh: &Mesh,
+    format: StlFormat,
+    record: Option<&DesignRecord>,
+    path: impl AsRef<Path>,
+) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("mesh");
+    mesh_solids(mesh)
+        .into_iter()
+        .map(|solid| {
+            let file = path.with_file_name(format!("{}_{}.stl", stem, solid.name));
+            write_stl(&[solid], format, record, &file)?;
+            Ok(file)
+        })
+        .collect()
+}
+
+/// Reads an ASCII (possibly multi-solid) or binary STL file
+pub fn read_stl(path: impl AsRef<Path>) -> io::Result<Vec<StlSolid>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    // Binary files may also start with "solid", the size tells them apart
+    let binary = bytes.len() >= 84
+        && bytes.len() == 84 + 50 * u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    if binary {
+        read_binary(&bytes)
+    } else {
+        read_ascii(BufReader::new(bytes.as_slice()))
+    }
+}
+
+/// Solids of a binary file: split by attribute word when written by `write_stl`, a single
+/// solid otherwise (other writers keep colours there)
+fn read_binary(bytes: &[u8]) -> io::Result<Vec<StlSolid>> {
+    // Anything after a NUL is not part of the names (see `write_stl`); older files start with "solid"
+    let header = String::from_utf8_lossy(&bytes[..80]);
+    let names = header.split('\0').next().unwrap_or_default();
+    let (names, indexed) = match names.strip_prefix(BINARY_HEADER) {
+        Some(names) => (names, true),
+        None => (names.strip_prefix("solid").unwrap_or_default(), false),
+    };
+
+    let float = |offset: usize| {
+        f32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]) as f64
+    };
+    let triangle = |offset: usize| {
+        [0, 1, 2].map(|v| {
+            let at = offset + 12 * v;
+            CartesianPoint { x: float(at), y: float(at + 4), z: float(at + 8) }
+        })
+    };
+    if !indexed {
+        let triangles = (84..bytes.len()).step_by(50).map(|offset| triangle(offset + 12)).collect();
+        return Ok(vec![StlSolid { name: names.trim().to_string(), triangles }]);
+    }
+
+    // Solids whose names did not fit in the header stay unnamed
+    let names: Vec<&str> = names.split_whitespace().collect();
+    let mut solids: Vec<StlSolid> = Vec::new();
+    for offset in (84..bytes.len()).step_by(50) {
+        let index = u16::from_le_bytes([bytes[offset + 48], bytes[offset + 49]]) as usize;
+        while solids.len() <= index {
+            let name = names.get(solids.len()).copied().unwrap_or_default();
+            solids.push(StlSolid { name: name.to_string(), triangles: Vec::new() });
+        }
+        solids[index].triangles.push(triangle(offset + 12)); // skip the normal
+    }
+    Ok(solids)
+}
+
+fn read_ascii(reader: impl BufRead) -> io::Result<Vec<StlSolid>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut solids = Vec::new();
+    let mut current: Option<StlSolid> = None;
+    let mut vertices: Vec<CartesianPoint> = Vec::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("endsolid") {
+            let solid = current.take().ok_or_else(|| invalid(format!("line {}: endsolid outside a solid", number + 1)))?;
+            if !vertices.is_empty() {
+                return Err(invalid(format!("line {}: incomplete facet in solid {}", number + 1, name.trim())));
+            }
+            solids.push(solid);
+        } else if let Some(name) = line.strip_prefix("solid") {
+            // The name is the first word, a design record may follow it
+            let name = name.split_whitespace().next().unwrap_or_default();
+            current = Some(StlSolid { name: name.to_string(), triangles: Vec::new() });
+        } else if let Some(coordinates) = line.strip_prefix("vertex") {
+            let values: Vec<f64> = coordinates
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|e| invalid(format!("line {}: {}", number + 1, e)))?;
+            if values.len() != 3 {
+                return Err(invalid(format!("line {}: expected 3 coordinates", number + 1)));
+            }
+            vertices.push(CartesianPoint { x: values[0], y: values[1], z: values[2] });
+            if vertices.len() == 3 {
+                let solid = current.as_mut().ok_or_else(|| invalid(format!("line {}: vertex outside a solid", number + 1)))?;
+                solid.triangles.push([vertices[0], vertices[1], vertices[2]]);
+                vertices.clear();
+            }
+        }
+    }
+
+    if current.is_some() {
+        return Err(invalid("missing endsolid".to_string()));
+    }
+    Ok(solids)
+}
+
+/// Welds the solids into one mesh, tagging each solid by its name (`Wall` when unknown)
+pub fn solids_to_mesh(solids: &[StlSolid]) -> Mesh {
+    let triangles: Vec<[CartesianPoint; 3]> = solids
+        .iter()
+        .flat_map(|solid| solid.triangles.iter().copied())
+        .collect();
+    let mut mesh = Mesh::from_triangles(&triangles, SurfaceTag::Wall);
+    mesh.tags = solids
+        .iter()
+        .flat_map(|solid| {
+            let tag = SurfaceTag::from_name(&solid.name).unwrap_or(SurfaceTag::Wall);
+            std::iter::repeat_n(tag, solid.triangles.len())
+        })
+        .collect();
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solids() -> Vec<StlSolid> {
+        let point = |x: f64, y: f64, z: f64| CartesianPoint { x, y, z };
+        vec![
+            StlSolid {
+                name: "wall".to_string(),
+                triangles: vec![
+                    [point(0.0, 0.0, 0.0), point(1.5, 0.0, 0.0), point(0.0, 2.25, 0.1)],
+                    [point(1.5, 0.0, 0.0), point(1.5, 2.25, -0.5), point(0.0, 2.25, 0.1)],
+                ],
+            },
+            StlSolid {
+                name: "source".to_string(),
+                triangles: vec![[point(0.1, 0.2, 0.3), point(-12.5, 7.0, 1.0), point(3.0, -4.0, 100.125)]],
+            },
+        ]
+    }
+
+    fn distance(a: &[CartesianPoint; 3], b: &[CartesianPoint; 3]) -> f64 {
+        (0..3).map(|k| (a[k] - b[k]).norm()).fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let path = std::env::temp_dir().join("compression_waveguide_ascii.stl");
+        write_stl(&solids(), StlFormat::Ascii, None, &path).unwrap();
+        let read = read_stl(&path).unwrap();
+        assert_eq!(read.len(), 2);
+        for (written, read) in solids().iter().zip(&read) {
+            assert_eq!(written.name, read.name);
+            assert_eq!(written.triangles.len(), read.triangles.len());
+            for (a, b) in written.triangles.iter().zip(&read.triangles) {
+                assert_eq!(distance(a, b), 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let path = std::env::temp_dir().join("compression_waveguide_binary.stl");
+        write_stl(&solids(), StlFormat::Binary, None, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(!bytes.starts_with(b"solid"));
+
+        let read = read_stl(&path).unwrap();
+        assert_eq!(read.len(), 2);
+        for (written, read) in solids().iter().zip(&read) {
+            assert_eq!(written.name, read.name);
+            assert_eq!(written.triangles.len(), read.triangles.len());
+            for (a, b) in written.triangles.iter().zip(&read.triangles) {
+                assert!(distance(a, b) < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn names_are_checked_and_cut_between_characters() {
+        let path = std::env::temp_dir().join("compression_waveguide_names.stl");
+        let mut spaced = solids();
+        spaced[0].name = "inner wall".to_string();
+        assert!(write_stl(&spaced, StlFormat::Ascii, None, &path).is_err());
+
+        // The second name does not fit whole after the first, its solid stays unnamed
+        let mut long = solids();
+        long[0].name = "é".repeat(25);
+        long[1].name = "ü".repeat(10);
+        write_stl(&long, StlFormat::Binary, None, &path).unwrap();
+        let read = read_stl(&path).unwrap();
+        assert_eq!(read[0].name, long[0].name);
+        assert_eq!(read[1].name, "");
+        assert_eq!(read[1].triangles.len(), 1);
+
+        assert_eq!(truncate("aé", 2), "a");
+    }
+}
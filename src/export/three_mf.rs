@@ -0,0 +1,171 @@
+use super::zip::write_zip;
+use crate::mesh::Mesh;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Object of a 3MF build, with metadata such as the design record that generated it
+/// (`("design", record.to_json())`, found again by `read_record`)
+#[derive(Debug, Clone)]
+pub struct ThreeMfObject {
+    pub name: String,
+    pub mesh: Mesh,
+    pub metadata: Vec<(String, String)>,
+}
+
+const CORE_NAMESPACE: &str = "http://schemas.microsoft.com/3dmanufacturing/core/2015/02";
+
+/// Namespace of the object metadata names
+const METADATA_NAMESPACE: &str = "urn:compression-waveguide:parameters";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// 3D model part of the package, all objects placed as they are in one build
+fn model_xml(objects: &[ThreeMfObject], title: &str) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<model unit=\"millimeter\" xml:lang=\"en-US\" xmlns=\"{}\" xmlns:cw=\"{}\">\n",
+        CORE_NAMESPACE, METADATA_NAMESPACE
+    ));
+    xml.push_str(&format!(" <metadata name=\"Title\">{}</metadata>\n", escape(title)));
+    xml.push_str(&format!(
+        " <metadata name=\"Application\">compression-waveguide {}</metadata>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    xml.push_str(" <resources>\n");
+    for (index, object) in objects.iter().enumerate() {
+        xml.push_str(&format!("  <object id=\"{}\" name=\"{}\" type=\"model\">\n", index + 1, escape(&object.name)));
+        if !object.metadata.is_empty() {
+            xml.push_str("   <metadatagroup>\n");
+            for (name, value) in &object.metadata {
+                xml.push_str(&format!(
+                    "    <metadata name=\"cw:{}\" preserve=\"1\">{}</metadata>\n",
+                    escape(name),
+                    escape(value)
+                ));
+            }
+            xml.push_str("   </metadatagroup>\n");
+        }
+        xml.push_str("   <mesh>\n    <vertices>\n");
+        for vertex in &object.mesh.vertices {
+            xml.push_str(&format!("     <vertex x=\"{}\" y=\"{}\" z=\"{}\"/>\n", vertex.x, vertex.y, vertex.z));
+        }
+        xml.push_str("    </vertices>\n    <triangles>\n");
+        for face in &object.mesh.triangles {
+            xml.push_str(&format!("     <triangle v1=\"{}\" v2=\"{}\" v3=\"{}\"/>\n", face[0], face[1], face[2]));
+        }
+        xml.push_str("    </triangles>\n   </mesh>\n  </object>\n");
+    }
+    xml.push_str(" </resources>\n <build>\n");
+    for index in 0..objects.len() {
+        xml.push_str(&format!("  <item objectid=\"{}\"/>\n", index + 1));
+    }
+    xml.push_str(" </build>\n</model>\n");
+    xml
+}
+
+/// Writes the objects as one build of a 3MF package in millimetres. The meshes must be closed
+/// with outward normals, as from `generate_solid`.
+pub fn write_3mf(objects: &[ThreeMfObject], title: &str, path: impl AsRef<Path>) -> io::Result<()> {
+    let content_types = concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\n",
+        " <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\n",
+        " <Default Extension=\"model\" ContentType=\"application/vnd.ms-package.3dmanufacturing-3dmodel+xml\"/>\n",
+        "</Types>\n"
+    );
+    let relationships = concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n",
+        " <Relationship Target=\"/3D/3dmodel.model\" Id=\"rel0\" Type=\"http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel\"/>\n",
+        "</Relationships>\n"
+    );
+    let model = model_xml(objects, title);
+
+    let mut file = BufWriter::new(File::create(path)?);
+    write_zip(
+        &[
+            ("[Content_Types].xml", content_types.as_bytes()),
+            ("_rels/.rels", relationships.as_bytes()),
+            ("3D/3dmodel.model", model.as_bytes()),
+        ],
+        &mut file,
+    )?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry_types::CartesianPoint;
+    use crate::mesh::SurfaceTag;
+
+    /// Fails unless `text` only uses the predefined entities
+    fn check_entities(text: &str) {
+        assert!(!text.contains('<'));
+        for (index, _) in text.match_indices('&') {
+            let rest = &text[index..];
+            assert!(["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"].iter().any(|entity| rest.starts_with(entity)), "{}", rest);
+        }
+    }
+
+    /// Checks the document is well formed and returns the names of its elements in order
+    fn elements(xml: &str) -> Vec<String> {
+        let mut rest = xml.strip_prefix("<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+        let (mut open, mut names): (Vec<String>, Vec<String>) = (Vec::new(), Vec::new());
+        while let Some(start) = rest.find('<') {
+            check_entities(&rest[..start]);
+            assert!(!open.is_empty() || rest[..start].trim().is_empty());
+            let end = start + rest[start..].find('>').unwrap();
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop().as_deref(), Some(name));
+                continue;
+            }
+            assert!(!open.is_empty() || names.is_empty(), "second root element");
+            let (tag, closed) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let (name, mut attributes) = tag.split_once(' ').unwrap_or((tag, ""));
+            while let Some((_, after)) = attributes.trim_start().split_once("=\"") {
+                let (value, after) = after.split_once('"').unwrap();
+                check_entities(value);
+                attributes = after;
+            }
+            assert!(attributes.trim().is_empty());
+            names.push(name.to_string());
+            if !closed {
+                open.push(name.to_string());
+            }
+        }
+        assert!(open.is_empty() && rest.trim().is_empty());
+        names
+    }
+
+    #[test]
+    fn model_is_well_formed() {
+        let point = |x: f64, y: f64, z: f64| CartesianPoint { x, y, z };
+        let (a, b, c, d) = (point(0.0, 0.0, 0.0), point(1.0, 0.0, 0.0), point(0.0, 1.0, 0.0), point(0.0, 0.0, 1.0));
+        let mesh = Mesh::from_triangles(&[[a, c, b], [a, b, d], [a, d, c], [b, c, d]], SurfaceTag::Wall);
+        let object = ThreeMfObject {
+            name: "tom's <horn> & \"baffle\"".to_string(),
+            mesh,
+            metadata: vec![("design".to_string(), r#"{"name":"a<b & c"}"#.to_string())],
+        };
+        let xml = model_xml(&[object.clone(), object], "Cléac'h & co");
+        let names = elements(&xml);
+        assert_eq!(names[0], "model");
+        let count = |element: &str| names.iter().filter(|name| *name == element).count();
+        assert_eq!((count("object"), count("vertex"), count("triangle"), count("item")), (2, 8, 8, 2));
+        assert!(xml.contains("name=\"tom&apos;s &lt;horn&gt; &amp; &quot;baffle&quot;\""));
+    }
+}
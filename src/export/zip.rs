@@ -0,0 +1,100 @@
+use std::io::{self, Write};
+
+/// CRC-32 (IEEE 802.3, reflected) as used by zip
+fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        *entry = (0..8).fold(n as u32, |c, _| if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 });
+    }
+    !data
+        .iter()
+        .fold(!0u32, |crc, &byte| table[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+/// Writes `entries` (path in the archive, contents) as a zip archive, stored uncompressed.
+/// Timestamps are left at the zip epoch, 1980-01-01.
+pub fn write_zip(entries: &[(&str, &[u8])], mut writer: impl Write) -> io::Result<()> {
+    const DOS_DATE: u16 = 0x21; // 1980-01-01
+    let mut central = Vec::new();
+    let mut offset = 0usize;
+
+    for (name, data) in entries {
+        let crc = crc32(data);
+        let mut local = Vec::new();
+        local.extend(0x0403_4b50u32.to_le_bytes());
+        local.extend(20u16.to_le_bytes()); // version needed
+        local.extend(0u16.to_le_bytes()); // flags
+        local.extend(0u16.to_le_bytes()); // stored
+        local.extend(0u16.to_le_bytes()); // time
+        local.extend(DOS_DATE.to_le_bytes());
+        local.extend(crc.to_le_bytes());
+        local.extend((data.len() as u32).to_le_bytes());
+        local.extend((data.len() as u32).to_le_bytes());
+        local.extend((name.len() as u16).to_le_bytes());
+        local.extend(0u16.to_le_bytes()); // extra field
+        local.extend(name.as_bytes());
+        writer.write_all(&local)?;
+        writer.write_all(data)?;
+
+        central.extend(0x0201_4b50u32.to_le_bytes());
+        central.extend(20u16.to_le_bytes()); // version made by
+        central.extend(20u16.to_le_bytes()); // version needed
+        central.extend(0u16.to_le_bytes());
+        central.extend(0u16.to_le_bytes());
+        central.extend(0u16.to_le_bytes());
+        central.extend(DOS_DATE.to_le_bytes());
+        central.extend(crc.to_le_bytes());
+        central.extend((data.len() as u32).to_le_bytes());
+        central.extend((data.len() as u32).to_le_bytes());
+        central.extend((name.len() as u16).to_le_bytes());
+        central.extend([0u8; 12]); // extra, comment, disk, internal and external attributes
+        central.extend((offset as u32).to_le_bytes());
+        central.extend(name.as_bytes());
+        offset += local.len() + data.len();
+    }
+
+    writer.write_all(&central)?;
+    let mut end = Vec::new();
+    end.extend(0x0605_4b50u32.to_le_bytes());
+    end.extend([0u8; 4]); // disk numbers
+    end.extend((entries.len() as u16).to_le_bytes());
+    end.extend((entries.len() as u16).to_le_bytes());
+    end.extend((central.len() as u32).to_le_bytes());
+    end.extend((offset as u32).to_le_bytes());
+    end.extend(0u16.to_le_bytes()); // comment
+    writer.write_all(&end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn central_directory_points_at_the_entries() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        let entries: [(&str, &[u8]); 3] = [("[Content_Types].xml", b"<Types/>"), ("3D/empty", b""), ("3D/model", b"123456789")];
+        let mut archive = Vec::new();
+        write_zip(&entries, &mut archive).unwrap();
+        let half = |at: usize| u16::from_le_bytes(archive[at..at + 2].try_into().unwrap()) as usize;
+        let word = |at: usize| u32::from_le_bytes(archive[at..at + 4].try_into().unwrap()) as usize;
+
+        let end = archive.len() - 22;
+        assert_eq!(word(end), 0x0605_4b50);
+        assert_eq!((half(end + 8), half(end + 10)), (3, 3));
+        let (size, mut at) = (word(end + 12), word(end + 16));
+        assert_eq!(at + size, end);
+        for (name, data) in entries {
+            assert_eq!(word(at), 0x0201_4b50);
+            let (crc, length, name_length, offset) = (word(at + 16), word(at + 24), half(at + 28), word(at + 42));
+            assert_eq!(&archive[at + 46..at + 46 + name_length], name.as_bytes());
+            assert_eq!((crc, length), (crc32(data) as usize, data.len()));
+
+            // The local header repeats the entry, its data right behind the name
+            assert_eq!(word(offset), 0x0403_4b50);
+            assert_eq!((word(offset + 14), word(offset + 18), half(offset + 26)), (crc, length, name_length));
+            let start = offset + 30 + name_length + half(offset + 28);
+            assert_eq!(&archive[start..start + length], data);
+            at += 46 + name_length;
+        }
+    }
+}
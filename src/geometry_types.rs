@@ -1,3 +1,7 @@
+use std::f64::consts::PI;
+use std::io;
+use std::ops::{Add, Mul, Sub};
+
 
 /// 3D Point in Cartesian coordinates
 #[derive(Debug, Clone, Copy)]
@@ -17,6 +21,76 @@ impl CartesianPoint {
             z,
         }
     }
+
+    /// Bend the z axis by `tilt` radians about the x axis (positive tilts towards -y).
+    /// The cross-section at `z` is rotated by `tilt * z / length`, so the plane z = `length`
+    /// ends up tilted by `tilt` while staying planar; beyond `length` the axis continues straight,
+    /// every cross-section parallel to that plane. Points past the centre of the bend fold over
+    /// (see `check_tilt`).
+    pub fn tilted(self, tilt: f64, length: f64) -> Self {
+        if tilt == 0.0 {
+            return self;
+        }
+        let bend_radius = length / tilt;
+        let phi = tilt * (self.z / length).clamp(0.0, 1.0);
+
+        // Centerline of the bent axis
+        let (axis_y, axis_z) = if self.z < 0.0 {
+            (0.0, self.z)
+        } else if self.z <= length {
+            (-bend_radius * (1.0 - phi.cos()), bend_radius * phi.sin())
+        } else {
+            (
+                -bend_radius * (1.0 - tilt.cos()) - (self.z - length) * tilt.sin(),
+                bend_radius * tilt.sin() + (self.z - length) * tilt.cos(),
+            )
+        };
+
+        Self {
+            x: self.x,
+            y: axis_y + self.y * phi.cos(),
+            z: axis_z + self.y * phi.sin(),
+        }
+    }
+}
+
+impl CartesianPoint {
+    pub fn dot(&self, other: &Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: &Self) -> Self {
+        Self {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+}
+
+impl Add for CartesianPoint {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self { x: self.x + other.x, y: self.y + other.y, z: self.z + other.z }
+    }
+}
+
+impl Sub for CartesianPoint {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self { x: self.x - other.x, y: self.y - other.y, z: self.z - other.z }
+    }
+}
+
+impl Mul<f64> for CartesianPoint {
+    type Output = Self;
+    fn mul(self, factor: f64) -> Self {
+        Self { x: self.x * factor, y: self.y * factor, z: self.z * factor }
+    }
 }
 
 /// Profile point in cylindrical coordinates
@@ -27,3 +101,100 @@ pub struct ProfilePoint {
     pub r: f64,     // radial distance from axis
     pub theta: f64, // azimuthal angle (constant for a single profile)
 }
+
+/// Fails when a profile point lies at or past the centre of the bend `CartesianPoint::tilted`
+/// gives the axis (r sin θ reaching `length / tilt`), where the bent cross-sections fold over
+/// each other
+pub fn check_tilt(profiles: &[Vec<ProfilePoint>], tilt: f64, length: f64) -> io::Result<()> {
+    if tilt == 0.0 {
+        return Ok(());
+    }
+    let bend_radius = length / tilt;
+    match profiles.iter().flatten().find(|point| point.r * point.theta.sin() / bend_radius >= 1.0) {
+        Some(point) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "tilt {} bends the axis with a radius of {} mm, inside the wall at r = {} mm, θ = {:.4}",
+                tilt,
+                bend_radius.abs(),
+                point.r,
+                point.theta
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Angle of the wall to the axis at each point of a profile, past π/2 where it rolls back
+pub fn wall_angles(profile: &[ProfilePoint]) -> Vec<f64> {
+    (0..profile.len())
+        .map(|j| {
+            let (a, b) = (profile[j.saturating_sub(1)], profile[(j + 1).min(profile.len() - 1)]);
+            (b.r - a.r).atan2(b.z - a.z)
+        })
+        .collect()
+}
+
+/// Texture coordinates of each point of a profile, u = θ / 2π around the axis (periodic with
+/// period 1, see `write_obj`) and v = z / `length` along it
+pub fn texture_coordinates(profile: &[ProfilePoint], length: f64) -> Vec<[f64; 2]> {
+    profile
+        .iter()
+        .map(|point| [point.theta.rem_euclid(2.0 * PI) / (2.0 * PI), point.z / length])
+        .collect()
+}
+
+/// Curvature of a profile at each point, from the circle through the point and its neighbours;
+/// positive where the wall bends away from the axis
+pub fn profile_curvatures(profile: &[ProfilePoint]) -> Vec<f64> {
+    let mut curvatures: Vec<f64> = profile
+        .windows(3)
+        .map(|points| {
+            let [a, b, c] = [points[0], points[1], points[2]];
+            let (dz1, dr1, dz2, dr2) = (b.z - a.z, b.r - a.r, c.z - b.z, c.r - b.r);
+            let lengths = dz1.hypot(dr1) * dz2.hypot(dr2) * (c.z - a.z).hypot(c.r - a.r);
+            if lengths > 0.0 {
+                2.0 * (dz1 * dr2 - dr1 * dz2) / lengths
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    if let (Some(&first), Some(&last)) = (curvatures.first(), curvatures.last()) {
+        curvatures.insert(0, first);
+        curvatures.push(last);
+    } else {
+        curvatures = vec![0.0; profile.len()];
+    }
+    curvatures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilted_mouth_stays_planar() {
+        let (tilt, length): (f64, f64) = (0.3, 100.0);
+        // Mouth plane normal and a point on it, the end of the bent axis
+        let normal = CartesianPoint { x: 0.0, y: -tilt.sin(), z: tilt.cos() };
+        let axis_end = CartesianPoint { x: 0.0, y: 0.0, z: length }.tilted(tilt, length);
+        for k in 0..16 {
+            let theta = 2.0 * PI * k as f64 / 16.0;
+            for (z, offset) in [(length, 0.0), (length + 20.0, 20.0)] {
+                let point = CartesianPoint::from_cylindrical(40.0 + 3.0 * k as f64, theta, z).tilted(tilt, length);
+                assert!(((point - axis_end).dot(&normal) - offset).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn tilt_folding_the_wall_is_rejected() {
+        let profile = |theta: f64| vec![ProfilePoint { z: 0.0, r: 20.0, theta }, ProfilePoint { z: 100.0, r: 90.0, theta }];
+        let profiles = vec![profile(PI / 2.0), profile(-PI / 2.0)];
+        // Bend radius 100 mm, then 50 mm inside the 90 mm mouth, either way
+        assert!(check_tilt(&profiles, 1.0, 100.0).is_ok());
+        assert!(check_tilt(&profiles, 2.0, 100.0).is_err());
+        assert!(check_tilt(&profiles, -2.0, 100.0).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+pub mod baffle;
+pub mod element_mesh;
+pub mod enclosure;
+pub mod export;
+pub mod geometry_types;
+pub mod mesh;
+pub mod models;
+pub mod nurbs;
+pub mod record;
+pub mod sanitize;
+pub mod solid;
+pub mod throat;
+pub mod trim;
+
+/// Speed of sound in air, in mm/s to match the model units
+pub const SPEED_OF_SOUND: f64 = 343_000.0;
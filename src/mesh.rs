@@ -0,0 +1,383 @@
+use crate::geometry_types::CartesianPoint;
+use crate::trim::Plane;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+
+/// Triangulates a closed family of profiles: `profiles[i][j]` is the j-th point along the
+/// i-th angle, and the last angle is connected back to the first one
+pub fn triangulate_profiles(profiles: &[Vec<CartesianPoint>]) -> Vec<[CartesianPoint; 3]> {
+    triangulate(profiles, true)
+}
+
+/// Triangulates an open family of profiles covering a sector, the first and last angles
+/// being the sector edges
+pub fn triangulate_sector(profiles: &[Vec<CartesianPoint>]) -> Vec<[CartesianPoint; 3]> {
+    triangulate(profiles, false)
+}
+
+fn triangulate(profiles: &[Vec<CartesianPoint>], closed: bool) -> Vec<[CartesianPoint; 3]> {
+    let mut triangles = Vec::new();
+    let sectors = if closed { profiles.len() } else { profiles.len().saturating_sub(1) };
+
+    for profile_idx in 0..sectors {
+        let next_profile_idx = (profile_idx + 1) % profiles.len();
+
+        let current_profile = &profiles[profile_idx];
+        let next_profile = &profiles[next_profile_idx];
+        let nb_axial_steps = current_profile.len().min(next_profile.len());
+
+        for point_idx in 0..nb_axial_steps.saturating_sub(1) {
+            let p0 = current_profile[point_idx];
+            let p1 = current_profile[point_idx + 1];
+            let p2 = next_profile[point_idx];
+            let p3 = next_profile[point_idx + 1];
+
+            // Triangle 1 (p0, p2, p1) - CCW for outward normals
+            triangles.push([p0, p2, p1]);
+
+            // Triangle 2 (p1, p2, p3)
+            triangles.push([p1, p2, p3]);
+        }
+    }
+
+    triangles
+}
+
+/// Surface a mesh triangle belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SurfaceTag {
+    Wall,
+    Baffle,
+    BaffleEdge,
+    BaffleSide,
+    EnclosureBack,
+    Source, // vibrating surface driving the simulation
+}
+
+impl SurfaceTag {
+    pub const ALL: [SurfaceTag; 6] = [
+        SurfaceTag::Wall,
+        SurfaceTag::Baffle,
+        SurfaceTag::BaffleEdge,
+        SurfaceTag::BaffleSide,
+        SurfaceTag::EnclosureBack,
+        SurfaceTag::Source,
+    ];
+
+    /// Name used for the surface in exported files
+    pub fn name(&self) -> &'static str {
+        match self {
+            SurfaceTag::Wall => "wall",
+            SurfaceTag::Baffle => "baffle",
+            SurfaceTag::BaffleEdge => "baffle_edge",
+            SurfaceTag::BaffleSide => "baffle_side",
+            SurfaceTag::EnclosureBack => "enclosure_back",
+            SurfaceTag::Source => "source",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tag| tag.name() == name)
+    }
+}
+
+/// Part of the waveguide a mesh covers, the rest following by mirroring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Symmetry {
+    /// Full turn, θ in [0, 2π)
+    #[default]
+    Full,
+    /// θ in [0, π], mirrored in the y = 0 plane
+    Half,
+    /// θ in [0, π/2], mirrored in the y = 0 and x = 0 planes
+    Quarter,
+    /// θ in [-π/2, π/2], the x ≥ 0 half, mirrored in the x = 0 plane
+    HalfX,
+}
+
+impl Symmetry {
+    /// Angular extent of the meshed sector
+    pub fn sector(&self) -> f64 {
+        match self {
+            Symmetry::Full => 2.0 * PI,
+            Symmetry::Half | Symmetry::HalfX => PI,
+            Symmetry::Quarter => PI / 2.0,
+        }
+    }
+
+    /// Angle the meshed sector starts from
+    pub fn start(&self) -> f64 {
+        match self {
+            Symmetry::HalfX => -PI / 2.0,
+            _ => 0.0,
+        }
+    }
+
+    /// Symmetry kept by a waveguide tilted by `tilt`. The tilt steers the axis towards -y, so the
+    /// y = 0 plane stops being a mirror plane while x = 0 stays one: tilted waveguides fall back
+    /// to the x ≥ 0 half.
+    pub fn for_tilt(self, tilt: f64) -> Self {
+        if tilt == 0.0 || self == Symmetry::Full {
+            self
+        } else {
+            Symmetry::HalfX
+        }
+    }
+
+    /// Pairs of angles whose profiles the mirror planes map onto each other: θ and -θ for the
+    /// y = 0 plane, θ and π - θ for the x = 0 plane
+    pub fn mirrors(&self, theta: f64) -> Vec<f64> {
+        match self {
+            Symmetry::Full => Vec::new(),
+            Symmetry::Half => vec![-theta],
+            Symmetry::Quarter => vec![-theta, PI - theta],
+            Symmetry::HalfX => vec![PI - theta],
+        }
+    }
+
+    /// Mirror planes, normals pointing to the side left out of the mesh
+    pub fn planes(&self) -> Vec<Plane> {
+        let origin = CartesianPoint { x: 0.0, y: 0.0, z: 0.0 };
+        let y_plane = Plane {
+            point: origin,
+            normal: CartesianPoint { x: 0.0, y: -1.0, z: 0.0 },
+        };
+        let x_plane = Plane {
+            point: origin,
+            normal: CartesianPoint { x: -1.0, y: 0.0, z: 0.0 },
+        };
+        match self {
+            Symmetry::Full => Vec::new(),
+            Symmetry::Half => vec![y_plane],
+            Symmetry::Quarter => vec![y_plane, x_plane],
+            Symmetry::HalfX => vec![x_plane],
+        }
+    }
+
+    /// Puts a point of the profile at `theta` exactly on the mirror plane when `theta` is a
+    /// sector edge and the point only misses the plane by rounding
+    pub fn snap(&self, point: CartesianPoint, theta: f64) -> CartesianPoint {
+        let rounding = 1e-9 * point.norm();
+        let (y_edge, x_edge) = match self {
+            Symmetry::Full => (false, false),
+            Symmetry::Half | Symmetry::Quarter => (
+                theta.abs() < 1e-12 || (theta - PI).abs() < 1e-12,
+                (theta - PI / 2.0).abs() < 1e-12,
+            ),
+            Symmetry::HalfX => (false, (theta.abs() - PI / 2.0).abs() < 1e-12),
+        };
+        if y_edge && point.y.abs() <= rounding {
+            CartesianPoint { y: 0.0, ..point }
+        } else if x_edge && point.x.abs() <= rounding {
+            CartesianPoint { x: 0.0, ..point }
+        } else {
+            point
+        }
+    }
+}
+
+/// Indexed triangle mesh with a surface tag per triangle
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub vertices: Vec<CartesianPoint>,
+    pub triangles: Vec<[usize; 3]>,
+    pub tags: Vec<SurfaceTag>,
+    pub symmetry: Symmetry,
+}
+
+impl Mesh {
+    /// Builds an indexed mesh from triangles, merging vertices with identical coordinates.
+    /// The triangles are taken as given: repair the profiles beforehand (see `repair_profiles`).
+    pub fn from_triangles(triangles: &[[CartesianPoint; 3]], tag: SurfaceTag) -> Self {
+        let mut mesh = Mesh::default();
+        let mut indices: HashMap<[u64; 3], usize> = HashMap::new();
+
+        for triangle in triangles {
+            let face = triangle.map(|vertex| {
+                // + 0.0 turns -0.0 into 0.0 so both share a key
+                let key = [vertex.x + 0.0, vertex.y + 0.0, vertex.z + 0.0].map(f64::to_bits);
+                *indices.entry(key).or_insert_with(|| {
+                    mesh.vertices.push(vertex);
+                    mesh.vertices.len() - 1
+                })
+            });
+            mesh.triangles.push(face);
+            mesh.tags.push(tag);
+        }
+
+        mesh
+    }
+
+    /// Wall mesh of a placed grid of profiles covering the `symmetry` sector, closed over the
+    /// full turn, vertices merged as in `from_triangles`
+    pub fn from_sector_grid(grid: &[Vec<CartesianPoint>], symmetry: Symmetry) -> Self {
+        let triangles = if symmetry == Symmetry::Full {
+            triangulate_profiles(grid)
+        } else {
+            triangulate_sector(grid)
+        };
+        Mesh {
+            symmetry,
+            ..Mesh::from_triangles(&triangles, SurfaceTag::Wall)
+        }
+    }
+
+    /// Indexed mesh of a grid of profiles (as `triangulate_profiles` or `triangulate_sector`
+    /// with `closed` false), vertex `i * points + j` being `grid[i][j]`
+    pub fn from_grid(grid: &[Vec<CartesianPoint>], closed: bool, tag: SurfaceTag) -> Self {
+        let points = grid.iter().map(Vec::len).min().unwrap_or(0);
+        let mut mesh = Mesh {
+            vertices: grid.iter().flat_map(|profile| profile[..points].iter().copied()).collect(),
+            ..Mesh::default()
+        };
+        let sectors = if closed { grid.len() } else { grid.len().saturating_sub(1) };
+        for i in 0..sectors {
+            let (current, next) = (i * points, (i + 1) % grid.len() * points);
+            for j in 0..points.saturating_sub(1) {
+                mesh.add_triangle([current + j, next + j, current + j + 1], tag);
+                mesh.add_triangle([current + j + 1, next + j, next + j + 1], tag);
+            }
+        }
+        mesh
+    }
+
+    /// Triangles as vertex triplets, as taken by the exporters
+    pub fn to_triangles(&self) -> Vec<[CartesianPoint; 3]> {
+        self.triangles
+            .iter()
+            .map(|face| face.map(|index| self.vertices[index]))
+            .collect()
+    }
+
+    pub fn add_vertex(&mut self, vertex: CartesianPoint) -> usize {
+        self.vertices.push(vertex);
+        self.vertices.len() - 1
+    }
+
+    pub fn add_triangle(&mut self, face: [usize; 3], tag: SurfaceTag) {
+        self.triangles.push(face);
+        self.tags.push(tag);
+    }
+
+    /// Joins two closed loops with the same number of vertices; `inner` is walked along its
+    /// edges as bounded by the existing triangles. Triangles collapsed by a repeated vertex are skipped.
+    pub fn add_strip(&mut self, inner: &[usize], outer: &[usize], tag: SurfaceTag) {
+        for k in 0..inner.len() {
+            let next = (k + 1) % inner.len();
+            for face in [
+                [inner[next], inner[k], outer[k]],
+                [inner[next], outer[k], outer[next]],
+            ] {
+                if face[0] != face[1] && face[1] != face[2] && face[2] != face[0] {
+                    self.add_triangle(face, tag);
+                }
+            }
+        }
+    }
+
+    /// Closes a boundary loop (oriented as from `boundary_loops`) with a fan around its centroid.
+    /// The loop must be star-shaped around its centroid.
+    pub fn add_cap(&mut self, boundary: &[usize], tag: SurfaceTag) {
+        let centroid = boundary
+            .iter()
+            .fold(CartesianPoint { x: 0.0, y: 0.0, z: 0.0 }, |sum, &i| sum + self.vertices[i])
+            * (1.0 / boundary.len() as f64);
+        let centre = self.add_vertex(centroid);
+        for k in 0..boundary.len() {
+            self.add_triangle([centre, boundary[(k + 1) % boundary.len()], boundary[k]], tag);
+        }
+    }
+
+    /// Enclosed volume of a closed mesh, positive when the normals point outward
+    pub fn signed_volume(&self) -> f64 {
+        self.triangles
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.map(|i| self.vertices[i]);
+                a.dot(&b.cross(&c)) / 6.0
+            })
+            .sum()
+    }
+
+    /// Unit normals at the vertices, averaged over the triangles around them weighted by area
+    pub fn vertex_normals(&self) -> Vec<CartesianPoint> {
+        let mut normals = vec![CartesianPoint { x: 0.0, y: 0.0, z: 0.0 }; self.vertices.len()];
+        for face in &self.triangles {
+            let [a, b, c] = face.map(|i| self.vertices[i]);
+            let normal = (b - a).cross(&(c - a));
+            for &i in face {
+                normals[i] = normals[i] + normal;
+            }
+        }
+        normals
+            .into_iter()
+            .map(|normal| {
+                let norm = normal.norm();
+                if norm > 0.0 {
+                    normal * (1.0 / norm)
+                } else {
+                    normal
+                }
+            })
+            .collect()
+    }
+
+    /// Reverses the winding of every triangle
+    pub fn flip(&mut self) {
+        for face in &mut self.triangles {
+            face.swap(1, 2);
+        }
+    }
+
+    /// Closed loops of boundary edges (edges used by a single triangle), each oriented like
+    /// the triangles it bounds
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let edges: HashSet<(usize, usize)> = self
+            .triangles
+            .iter()
+            .flat_map(|face| [(face[0], face[1]), (face[1], face[2]), (face[2], face[0])])
+            .collect();
+        let mut next: HashMap<usize, usize> = edges
+            .iter()
+            .filter(|(a, b)| !edges.contains(&(*b, *a)))
+            .map(|&(a, b)| (a, b))
+            .collect();
+
+        let mut loops = Vec::new();
+        while let Some(&start) = next.keys().min() {
+            let mut boundary = vec![start];
+            let mut current = next.remove(&start).unwrap();
+            while current != start {
+                boundary.push(current);
+                match next.remove(&current) {
+                    Some(vertex) => current = vertex,
+                    None => break, // open chain on a non-manifold mesh
+                }
+            }
+            loops.push(boundary);
+        }
+
+        loops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_leaves_points_off_the_mirror_planes() {
+        let on_plane = CartesianPoint::from_cylindrical(50.0, PI, 10.0);
+        assert_eq!(Symmetry::Half.snap(on_plane, PI).y, 0.0);
+
+        // Tilting moves the θ = π point off the y = 0 plane
+        let tilted = on_plane.tilted(0.3, 100.0);
+        assert_eq!(Symmetry::Half.snap(tilted, PI).y, tilted.y);
+        assert_eq!(Symmetry::Half.for_tilt(0.3), Symmetry::HalfX);
+        assert_eq!(Symmetry::Quarter.for_tilt(0.0), Symmetry::Quarter);
+
+        // The x = 0 plane stays a mirror plane under tilt
+        let side = CartesianPoint::from_cylindrical(50.0, -PI / 2.0, 10.0).tilted(0.3, 100.0);
+        assert_eq!(Symmetry::HalfX.snap(side, -PI / 2.0).x, 0.0);
+    }
+}
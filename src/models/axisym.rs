@@ -1,12 +1,16 @@
-use crate::models::OblateSpheroidWG;
+use crate::models::{OblateSpheroidWG, Termination};
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AxisymOSWG {
     pub k: f64,
     pub r_init: f64,
     pub alpha_init: f64,
+    pub tilt: f64,
     pub s: f64,
     pub q: f64,
     pub n: f64,
+    pub termination: Option<Termination>,
     pub alpha: f64,
 }
 
@@ -14,9 +18,11 @@ impl OblateSpheroidWG for AxisymOSWG {
     fn k(&self) -> f64 { self.k }
     fn r_init(&self) -> f64 { self.r_init }
     fn alpha_init(&self) -> f64 { self.alpha_init }
-    fn s(&self) -> f64 { self.s }
-    fn q(&self) -> f64 { self.q }
-    fn n(&self) -> f64 { self.n }
+    fn tilt(&self) -> f64 { self.tilt }
+    fn s(&self, _theta: f64) -> f64 { self.s }
+    fn q(&self, _theta: f64) -> f64 { self.q }
+    fn n(&self, _theta: f64) -> f64 { self.n }
+    fn termination(&self) -> Option<Termination> { self.termination }
 
     fn calculate_tan_alpha(&self, _theta: f64, _l:f64) -> f64 {
         self.alpha.tan()
@@ -1,11 +1,16 @@
-use crate::models::OblateSpheroidClothoidWG;
+use crate::geometry_types::ProfilePoint;
+use crate::models::{OblateSpheroidClothoidWG, Termination, Waveguide};
+use crate::throat::ThroatCap;
+use std::io;
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AxisymOSCWG {
     pub k: f64,
     pub r_init: f64,
     pub alpha_init: f64,
-    pub term_end_radius: f64,
-    pub term_length: f64,
+    pub tilt: f64,
+    pub termination: Termination,
     pub alpha: f64,
 }
 
@@ -19,14 +24,75 @@ impl OblateSpheroidClothoidWG for AxisymOSCWG {
     fn alpha_init(&self) -> f64 {
         self.alpha_init
     }
-    fn term_length(&self) -> f64 {
-        self.term_length
+    fn tilt(&self) -> f64 {
+        self.tilt
     }
-    fn term_end_radius(&self) -> f64 {
-        self.term_end_radius
+    fn termination(&self) -> Termination {
+        self.termination
     }
 
     fn calculate_tan_alpha(&self, _theta: f64, _l: f64) -> f64 {
         self.alpha.tan()
     }
 }
+
+impl Waveguide for AxisymOSCWG {
+    fn tilt(&self) -> f64 {
+        self.tilt
+    }
+
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        OblateSpheroidClothoidWG::generate_profile(self, length, theta, resolution)
+    }
+
+    fn throat_cap(&self) -> ThroatCap {
+        ThroatCap::wavefront(self.r_init, self.alpha_init)
+    }
+
+    fn lip_thickness(&self) -> Option<f64> {
+        self.termination.lip_thickness()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{Mesh, SurfaceTag};
+    use crate::models::TerminationEnd;
+
+    #[test]
+    fn unsolvable_termination_is_reported() {
+        let waveguide = AxisymOSCWG {
+            k: 1.0,
+            r_init: 12.7,
+            alpha_init: 0.0,
+            tilt: 0.0,
+            termination: Termination::Clothoid(TerminationEnd::MouthRadius { radius: 150.0, end_radius: 20.0 }),
+            alpha: 0.5,
+        };
+        assert!(waveguide.generate_mesh(100.0, 16, 21).is_ok());
+        let waveguide = AxisymOSCWG {
+            termination: Termination::Clothoid(TerminationEnd::MouthRadius { radius: 150.0, end_radius: 0.0 }),
+            ..waveguide
+        };
+        assert!(waveguide.generate_mesh(100.0, 16, 21).is_err());
+    }
+
+    #[test]
+    fn lip_solid_takes_the_lip_thickness() {
+        let waveguide = AxisymOSCWG {
+            k: 1.0,
+            r_init: 12.7,
+            alpha_init: 0.0,
+            tilt: 0.0,
+            termination: Termination::Lip { radius: 10.0, thickness: 3.0 },
+            alpha: 0.5,
+        };
+        let solid = waveguide.generate_solid(100.0, 16, 21, 3.0).unwrap();
+        let mesh = Mesh::from_triangles(&solid, SurfaceTag::Wall);
+        assert!(mesh.boundary_loops().is_empty());
+        assert!(mesh.vertices.iter().all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite()));
+        assert!(waveguide.generate_solid(100.0, 16, 21, 2.0).is_err());
+        assert!(waveguide.fit_solid_surfaces(100.0, 2.0, 0.1).is_err());
+    }
+}
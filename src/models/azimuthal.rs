@@ -0,0 +1,132 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::f64::consts::PI;
+use std::io;
+
+/// Parameter value that may vary with the azimuth θ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AzimuthalValue {
+    /// Same value for every θ
+    Constant(f64),
+    /// Polar radius of the ellipse with half-axes `h` (θ = 0) and `v` (θ = π/2)
+    Elliptical { h: f64, v: f64 },
+    /// Polar radius of the rectangle with half-sides `h` (θ = 0) and `v` (θ = π/2)
+    Rectangular { h: f64, v: f64 },
+    /// User table of (θ, value) pairs sorted by θ in [0, 2π), linearly interpolated and periodic.
+    /// Build it with `AzimuthalValue::table`.
+    Table(#[serde(deserialize_with = "deserialize_table")] Vec<(f64, f64)>),
+}
+
+impl AzimuthalValue {
+    /// Table of (θ, value) pairs in any order, θ wrapped to [0, 2π). Rejects an empty table,
+    /// non-finite entries and angles given twice.
+    pub fn table(entries: impl IntoIterator<Item = (f64, f64)>) -> io::Result<Self> {
+        sorted_table(entries.into_iter().collect()).map(AzimuthalValue::Table)
+    }
+
+    pub fn at(&self, theta: f64) -> f64 {
+        match self {
+            AzimuthalValue::Constant(value) => *value,
+            AzimuthalValue::Elliptical { h, v } => {
+                (h * v) / ((v * theta.cos()).powi(2) + (h * theta.sin()).powi(2)).sqrt()
+            }
+            AzimuthalValue::Rectangular { h, v } => {
+                (h / theta.cos().abs()).min(v / theta.sin().abs())
+            }
+            AzimuthalValue::Table(table) => interpolate_periodic(table, theta),
+        }
+    }
+
+    /// Smallest and largest value over the turn
+    pub fn range(&self) -> (f64, f64) {
+        match self {
+            AzimuthalValue::Constant(value) => (*value, *value),
+            AzimuthalValue::Elliptical { h, v } => (h.abs().min(v.abs()), h.abs().max(v.abs())),
+            AzimuthalValue::Rectangular { h, v } => (h.abs().min(v.abs()), h.hypot(*v)),
+            AzimuthalValue::Table(table) => table
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &(_, value)| (low.min(value), high.max(value))),
+        }
+    }
+}
+
+impl From<f64> for AzimuthalValue {
+    fn from(value: f64) -> Self {
+        AzimuthalValue::Constant(value)
+    }
+}
+
+/// Entries wrapped to [0, 2π) and sorted by θ, checked as described in `AzimuthalValue::table`
+fn sorted_table(entries: Vec<(f64, f64)>) -> io::Result<Vec<(f64, f64)>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    if entries.is_empty() {
+        return Err(invalid("empty azimuthal table".to_string()));
+    }
+    if let Some((theta, value)) = entries.iter().find(|(theta, value)| !theta.is_finite() || !value.is_finite()) {
+        return Err(invalid(format!("non-finite azimuthal table entry ({}, {})", theta, value)));
+    }
+
+    let mut table: Vec<(f64, f64)> = entries
+        .into_iter()
+        .map(|(theta, value)| (theta.rem_euclid(2.0 * PI), value))
+        .collect();
+    table.sort_by(|a, b| a.0.total_cmp(&b.0));
+    // Neighbours in turn, the last one wrapping to the first (0 and 2π are the same angle)
+    let duplicate = (0..table.len()).find(|&k| {
+        let next = if k + 1 < table.len() { table[k + 1].0 } else { table[0].0 + 2.0 * PI };
+        table.len() > 1 && next - table[k].0 < 1e-12
+    });
+    if let Some(k) = duplicate {
+        return Err(invalid(format!("azimuthal table gives θ = {} twice", table[k].0)));
+    }
+    Ok(table)
+}
+
+fn deserialize_table<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(f64, f64)>, D::Error> {
+    sorted_table(Vec::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+/// Linear interpolation in a (θ, value) table as built by `AzimuthalValue::table`, wrapping
+/// around at 2π
+fn interpolate_periodic(table: &[(f64, f64)], theta: f64) -> f64 {
+    if table.is_empty() {
+        return f64::NAN;
+    }
+    let theta = theta.rem_euclid(2.0 * PI);
+    let next_idx = table.iter().position(|&(t, _)| t > theta).unwrap_or(table.len());
+
+    // Neighbours of theta, the previous one shifted by -2π or the next one by +2π when wrapping
+    let (t0, v0) = match next_idx {
+        0 => (table[table.len() - 1].0 - 2.0 * PI, table[table.len() - 1].1),
+        i => table[i - 1],
+    };
+    let (t1, v1) = match table.get(next_idx) {
+        Some(&entry) => entry,
+        None => (table[0].0 + 2.0 * PI, table[0].1),
+    };
+
+    if t1 - t0 <= 0.0 {
+        return v0;
+    }
+    v0 + (v1 - v0) * (theta - t0) / (t1 - t0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_sorted_and_wraps() {
+        let value = AzimuthalValue::table([(PI, 3.0), (-PI / 2.0, 4.0), (0.0, 1.0)]).unwrap();
+        assert!((value.at(PI / 2.0) - 2.0).abs() < 1e-12);
+        assert!((value.at(1.75 * PI) - 2.5).abs() < 1e-12);
+        assert!((value.at(-PI / 2.0) - 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn invalid_tables_are_rejected() {
+        assert!(AzimuthalValue::table([]).is_err());
+        assert!(AzimuthalValue::table([(0.0, 1.0), (2.0 * PI, 2.0)]).is_err());
+        assert!(AzimuthalValue::table([(0.0, f64::NAN)]).is_err());
+        assert!(serde_json::from_str::<AzimuthalValue>(r#"{"Table":[]}"#).is_err());
+    }
+}
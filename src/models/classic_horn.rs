@@ -0,0 +1,201 @@
+use crate::geometry_types::ProfilePoint;
+use crate::models::le_cleach::{le_cleach_wall, spline};
+use crate::models::spline::MonotoneCubic;
+use crate::models::Waveguide;
+use crate::SPEED_OF_SOUND;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::io::{self, Error, ErrorKind};
+
+/// Flare law of a classic horn
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum HornProfile {
+    /// Straight wall; the apex lies c / (2π fc) behind the throat
+    Conical,
+    /// r = r_init * exp(k z), with k = 2π fc / c
+    Exponential,
+    /// Hyperbolic-exponential: r = r_init * (cosh(k z) + T sinh(k z))
+    Hypex { t: f64 },
+    /// Tractrix with mouth radius c / (2π fc), truncated at `mouth_radius` if smaller
+    Tractrix,
+    /// Spherical wavefronts normal to the wall, their area following the hypex law along the wall
+    LeCleach { t: f64 },
+}
+
+/// Axisymmetric horn defined by throat radius, cutoff frequency and mouth radius.
+/// Build it with `ClassicHornWG::new`, records are checked the same way when read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "ClassicHornParameters")]
+pub struct ClassicHornWG {
+    r_init: f64,
+    cutoff_frequency: f64, // Hz
+    mouth_radius: f64,
+    pub tilt: f64,
+    profile: HornProfile,
+    /// Le Cléac'h wall, integrated once by `new`
+    #[serde(skip_serializing)]
+    le_cleach: Option<MonotoneCubic>,
+}
+
+/// Unchecked fields of `ClassicHornWG`, as deserialized
+#[derive(Deserialize)]
+struct ClassicHornParameters {
+    r_init: f64,
+    cutoff_frequency: f64,
+    mouth_radius: f64,
+    tilt: f64,
+    profile: HornProfile,
+}
+
+impl TryFrom<ClassicHornParameters> for ClassicHornWG {
+    type Error = Error;
+
+    fn try_from(parameters: ClassicHornParameters) -> std::io::Result<Self> {
+        Self::new(
+            parameters.r_init,
+            parameters.cutoff_frequency,
+            parameters.mouth_radius,
+            parameters.tilt,
+            parameters.profile,
+        )
+    }
+}
+
+impl ClassicHornWG {
+    /// Rejects non-positive sizes, a mouth no wider than the throat, a hypex (or Le Cléac'h)
+    /// T of -1 or less, whose area shrinks from the throat, and, for a tractrix, a throat at
+    /// or past the tractrix mouth radius c / (2π fc)
+    pub fn new(
+        r_init: f64,
+        cutoff_frequency: f64,
+        mouth_radius: f64,
+        tilt: f64,
+        profile: HornProfile,
+    ) -> std::io::Result<Self> {
+        let mut horn = Self { r_init, cutoff_frequency, mouth_radius, tilt, profile, le_cleach: None };
+        let invalid = |message: String| Err(Error::new(ErrorKind::InvalidInput, message));
+        if !(r_init > 0.0 && cutoff_frequency > 0.0) {
+            return invalid(format!("throat radius {r_init} and cutoff frequency {cutoff_frequency} must be positive"));
+        }
+        if mouth_radius.is_nan() || mouth_radius <= r_init {
+            return invalid(format!("mouth radius {mouth_radius} must exceed the throat radius {r_init}"));
+        }
+        if let HornProfile::Hypex { t } | HornProfile::LeCleach { t } = profile {
+            if t.is_nan() || t <= -1.0 {
+                return invalid(format!("hypex T = {t} must exceed -1"));
+            }
+        }
+        if let HornProfile::Tractrix = profile {
+            let tractrix_mouth = 1.0 / horn.flare_constant();
+            if r_init >= tractrix_mouth {
+                return invalid(format!(
+                    "throat radius {r_init} must be below the tractrix mouth radius {tractrix_mouth} at {cutoff_frequency} Hz"
+                ));
+            }
+        }
+        if let HornProfile::LeCleach { t } = profile {
+            horn.le_cleach = Some(spline(&le_cleach_wall(r_init, horn.flare_constant(), t, mouth_radius, f64::INFINITY)));
+        }
+        Ok(horn)
+    }
+
+    /// Wavenumber at the cutoff frequency (1/mm)
+    fn flare_constant(&self) -> f64 {
+        2.0 * PI * self.cutoff_frequency / SPEED_OF_SOUND
+    }
+
+    /// Axial length from the throat to the mouth
+    pub fn length(&self) -> f64 {
+        let k = self.flare_constant();
+        let ratio = self.mouth_radius / self.r_init;
+        match self.profile {
+            HornProfile::Conical => (self.mouth_radius - self.r_init) / (self.r_init * k),
+            HornProfile::Exponential => ratio.ln() / k,
+            HornProfile::Hypex { t } => {
+                // cosh(u) + T sinh(u) = ratio is a quadratic in exp(u)
+                ((ratio + (ratio.powi(2) - 1.0 + t.powi(2)).sqrt()) / (1.0 + t)).ln() / k
+            }
+            HornProfile::Tractrix => {
+                tractrix_position(1.0 / k, self.r_init) - tractrix_position(1.0 / k, self.tractrix_mouth())
+            }
+            HornProfile::LeCleach { .. } => self.le_cleach_spline().end().0,
+        }
+    }
+
+    fn tractrix_mouth(&self) -> f64 {
+        self.mouth_radius.min(1.0 / self.flare_constant())
+    }
+
+    pub fn radial_distance(&self, z: f64) -> f64 {
+        let k = self.flare_constant();
+        match self.profile {
+            HornProfile::Conical => self.r_init * (1.0 + k * z),
+            HornProfile::Exponential => self.r_init * (k * z).exp(),
+            HornProfile::Hypex { t } => self.r_init * ((k * z).cosh() + t * (k * z).sinh()),
+            HornProfile::Tractrix => {
+                // Invert the axial position by bisection, z grows monotonically with r
+                let mouth = 1.0 / k;
+                let throat_position = tractrix_position(mouth, self.r_init);
+                let (mut low, mut high) = (self.r_init, self.tractrix_mouth());
+                for _ in 0..60 {
+                    let mid = (low + high) / 2.0;
+                    if throat_position - tractrix_position(mouth, mid) < z {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                (low + high) / 2.0
+            }
+            HornProfile::LeCleach { .. } => self.le_cleach_spline().eval(z),
+        }
+    }
+
+    fn le_cleach_spline(&self) -> &MonotoneCubic {
+        self.le_cleach.as_ref().expect("ClassicHornWG::new integrates the Le Cléac'h wall")
+    }
+}
+
+/// Distance from the mouth plane of a tractrix of mouth radius `mouth` at radius `r`
+fn tractrix_position(mouth: f64, r: f64) -> f64 {
+    let root = (mouth.powi(2) - r.powi(2)).max(0.0).sqrt();
+    mouth * ((mouth + root) / r).ln() - root
+}
+
+impl Waveguide for ClassicHornWG {
+    fn tilt(&self) -> f64 {
+        self.tilt
+    }
+
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        Ok((0..resolution)
+            .map(|i| {
+                let z = length * (i as f64) / ((resolution - 1) as f64);
+                ProfilePoint { z, r: self.radial_distance(z), theta }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tractrix_throat_must_fit_the_tractrix() {
+        // c / (2π fc) is about 109 mm at 500 Hz
+        assert!(ClassicHornWG::new(12.7, 500.0, 150.0, 0.0, HornProfile::Tractrix).is_ok());
+        assert!(ClassicHornWG::new(120.0, 500.0, 150.0, 0.0, HornProfile::Tractrix).is_err());
+        assert!(ClassicHornWG::new(12.7, 0.0, 150.0, 0.0, HornProfile::Exponential).is_err());
+        assert!(ClassicHornWG::new(12.7, 500.0, 10.0, 0.0, HornProfile::Conical).is_err());
+    }
+
+    #[test]
+    fn hypex_needs_t_above_minus_one() {
+        assert!(ClassicHornWG::new(12.7, 500.0, 150.0, 0.0, HornProfile::Hypex { t: -0.5 }).is_ok());
+        assert!(ClassicHornWG::new(12.7, 500.0, 150.0, 0.0, HornProfile::Hypex { t: -1.0 }).is_err());
+        assert!(ClassicHornWG::new(12.7, 500.0, 150.0, 0.0, HornProfile::LeCleach { t: -2.0 }).is_err());
+        let record = r#"{"r_init":12.7,"cutoff_frequency":500.0,"mouth_radius":150.0,"tilt":0.0,"profile":{"Hypex":{"t":-1.5}}}"#;
+        assert!(serde_json::from_str::<ClassicHornWG>(record).is_err());
+    }
+}
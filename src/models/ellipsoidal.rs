@@ -1,12 +1,16 @@
-use crate::models::OblateSpheroidWG;
+use crate::models::{AzimuthalValue, OblateSpheroidWG, Termination};
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EllipsoidalOSWG {
     pub k: f64,
     pub r_init: f64,
     pub alpha_init: f64,
-    pub s: f64,
-    pub q: f64,
-    pub n: f64,
+    pub tilt: f64,
+    pub s: AzimuthalValue,
+    pub q: AzimuthalValue,
+    pub n: AzimuthalValue,
+    pub termination: Option<Termination>,
     pub alpha_h: f64,
     pub alpha_v: f64,
 }
@@ -15,9 +19,11 @@ impl OblateSpheroidWG for EllipsoidalOSWG {
     fn k(&self) -> f64 { self.k }
     fn r_init(&self) -> f64 { self.r_init }
     fn alpha_init(&self) -> f64 { self.alpha_init }
-    fn s(&self) -> f64 { self.s }
-    fn q(&self) -> f64 { self.q }
-    fn n(&self) -> f64 { self.n }
+    fn tilt(&self) -> f64 { self.tilt }
+    fn s(&self, theta: f64) -> f64 { self.s.at(theta) }
+    fn q(&self, theta: f64) -> f64 { self.q.at(theta) }
+    fn n(&self, theta: f64) -> f64 { self.n.at(theta) }
+    fn termination(&self) -> Option<Termination> { self.termination }
 
     fn calculate_tan_alpha(&self, theta: f64, _l:f64) -> f64 {
         let h_axis = self.alpha_h.tan();
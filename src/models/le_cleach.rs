@@ -0,0 +1,254 @@
+use crate::geometry_types::ProfilePoint;
+use crate::models::spline::MonotoneCubic;
+use crate::models::{AzimuthalValue, Waveguide};
+use crate::SPEED_OF_SOUND;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::io::{self, Error, ErrorKind};
+
+/// Mouth radii, spread over the range of a non-circular mouth, at which `LeCleachWG` solves a wall
+const WALL_SAMPLES: usize = 9;
+
+/// Le Cléac'h (JMLC) horn with an optional non-circular mouth.
+/// The horizontal wall (θ = 0) is integrated with the cutoff frequency and sets the length; like
+/// the coverage angle of `EllipsoidalOSWG`, the cutoff then varies with θ so that the wall of
+/// each azimuth is a Le Cléac'h wall ending on `mouth_radius.at(θ)` at that length.
+/// Build it with `LeCleachWG::new`, which integrates the walls once; records are checked and
+/// integrated the same way when read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "LeCleachParameters")]
+pub struct LeCleachWG {
+    r_init: f64,
+    cutoff_frequency: f64, // Hz
+    t: f64,                // expansion parameter of the hypex area law
+    mouth_radius: AzimuthalValue,
+    pub tilt: f64,
+    /// Axial length of every wall
+    #[serde(skip_serializing)]
+    length: f64,
+    /// Walls solved for increasing mouth radii over the range of `mouth_radius`
+    #[serde(skip_serializing)]
+    walls: Vec<(f64, MonotoneCubic)>,
+}
+
+/// Unchecked fields of `LeCleachWG`, as deserialized
+#[derive(Deserialize)]
+struct LeCleachParameters {
+    r_init: f64,
+    cutoff_frequency: f64,
+    t: f64,
+    mouth_radius: AzimuthalValue,
+    tilt: f64,
+}
+
+impl TryFrom<LeCleachParameters> for LeCleachWG {
+    type Error = Error;
+
+    fn try_from(parameters: LeCleachParameters) -> std::io::Result<Self> {
+        Self::new(
+            parameters.r_init,
+            parameters.cutoff_frequency,
+            parameters.t,
+            parameters.mouth_radius,
+            parameters.tilt,
+        )
+    }
+}
+
+impl LeCleachWG {
+    /// Rejects non-positive sizes, a T of -1 or less, a mouth no wider than the throat at some
+    /// angle, and mouths the walls turn parallel to the mouth plane before reaching
+    pub fn new(
+        r_init: f64,
+        cutoff_frequency: f64,
+        t: f64,
+        mouth_radius: AzimuthalValue,
+        tilt: f64,
+    ) -> std::io::Result<Self> {
+        let invalid = |message: String| Err(Error::new(ErrorKind::InvalidInput, message));
+        if !(r_init > 0.0 && cutoff_frequency > 0.0) {
+            return invalid(format!("throat radius {r_init} and cutoff frequency {cutoff_frequency} must be positive"));
+        }
+        if t.is_nan() || t <= -1.0 {
+            return invalid(format!("hypex T = {t} must exceed -1"));
+        }
+        let (narrowest, widest) = mouth_radius.range();
+        if narrowest.is_nan() || narrowest <= r_init || !widest.is_finite() {
+            return invalid(format!("mouth radius {narrowest} must exceed the throat radius {r_init} at every angle"));
+        }
+
+        let k = 2.0 * PI * cutoff_frequency / SPEED_OF_SOUND;
+        let horizontal = mouth_radius.at(0.0);
+        let wall = le_cleach_wall(r_init, k, t, horizontal, f64::INFINITY);
+        let (length, end) = wall[wall.len() - 1];
+        if end < horizontal * (1.0 - 1e-9) {
+            return invalid(format!(
+                "the wall turns parallel to the mouth plane at r = {end}, before the mouth radius {horizontal}"
+            ));
+        }
+
+        let samples = if widest - narrowest > 1e-9 * widest { WALL_SAMPLES } else { 1 };
+        let walls = (0..samples)
+            .map(|j| {
+                let mouth = narrowest + (widest - narrowest) * j as f64 / (samples - 1).max(1) as f64;
+                let wall = wall_to_length(r_init, k, t, mouth, length)?;
+                Ok((mouth, spline(&wall)))
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self { r_init, cutoff_frequency, t, mouth_radius, tilt, length, walls })
+    }
+
+    /// Axial length from the throat to the mouth
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Radius at `z` of the wall ending on `mouth`, interpolated between the solved walls
+    pub fn radial_distance(&self, z: f64, mouth: f64) -> f64 {
+        if self.walls.len() == 1 {
+            return self.walls[0].1.eval(z);
+        }
+        MonotoneCubic::new(
+            self.walls.iter().map(|(mouth, _)| *mouth).collect(),
+            self.walls.iter().map(|(_, wall)| wall.eval(z)).collect(),
+        )
+        .eval(mouth)
+    }
+}
+
+/// Wall with the cutoff, searched by bisection, that reaches `mouth_radius` at `length`
+fn wall_to_length(r_init: f64, k: f64, t: f64, mouth_radius: f64, length: f64) -> io::Result<Vec<(f64, f64)>> {
+    // Past `length` without reaching the mouth radius the cutoff is too low
+    let too_low = |k: f64| le_cleach_wall(r_init, k, t, mouth_radius, length).last().unwrap().0 >= length;
+    let (mut low, mut high) = (0.0, k);
+    while too_low(high) && high < 1e6 * k {
+        (low, high) = (high, 2.0 * high);
+    }
+    while high - low > 1e-12 * high {
+        let mid = (low + high) / 2.0;
+        if too_low(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    let mut wall = le_cleach_wall(r_init, high, t, mouth_radius, length);
+    let (end_z, end_r) = wall[wall.len() - 1];
+    if end_r < mouth_radius * (1.0 - 1e-9) || end_z < length * (1.0 - 1e-6) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("no Le Cléac'h wall reaches the mouth radius {mouth_radius} at the length {length}"),
+        ));
+    }
+    // Stretch the last 1e-6 so every wall ends on the same plane
+    for point in &mut wall {
+        point.0 *= length / end_z;
+    }
+    Ok(wall)
+}
+
+pub(crate) fn spline(wall: &[(f64, f64)]) -> MonotoneCubic {
+    MonotoneCubic::new(
+        wall.iter().map(|p| p.0).collect(),
+        wall.iter().map(|p| p.1).collect(),
+    )
+}
+
+/// Integrates the Le Cléac'h wall as (z, r) points, from the throat until the wall reaches
+/// `mouth_radius` or `max_length`, or turns parallel to the mouth plane.
+/// Each wavefront is a spherical cap normal to the wall: with a wall angle φ at radius r its
+/// area is 2π r² / (1 + cos φ), which is set to the hypex area law along the wall length.
+pub(crate) fn le_cleach_wall(r_init: f64, k: f64, t: f64, mouth_radius: f64, max_length: f64) -> Vec<(f64, f64)> {
+    let step = r_init / 500.0;
+    let throat_area = PI * r_init.powi(2);
+    let wall_angle = |l: f64, r: f64| {
+        let area = throat_area * ((k * l).cosh() + t * (k * l).sinh()).powi(2);
+        (2.0 * PI * r.powi(2) / area - 1.0).clamp(0.0, 1.0).acos()
+    };
+
+    let mut wall = vec![(0.0, r_init)];
+    let (mut l, mut z, mut r) = (0.0, 0.0, r_init);
+    while r < mouth_radius && z < max_length {
+        // RK4 step of (dz, dr)/dl = (cos φ, sin φ)
+        let phi1 = wall_angle(l, r);
+        let phi2 = wall_angle(l + step / 2.0, r + step / 2.0 * phi1.sin());
+        let phi3 = wall_angle(l + step / 2.0, r + step / 2.0 * phi2.sin());
+        let phi4 = wall_angle(l + step, r + step * phi3.sin());
+        let dz = step / 6.0 * (phi1.cos() + 2.0 * phi2.cos() + 2.0 * phi3.cos() + phi4.cos());
+        let dr = step / 6.0 * (phi1.sin() + 2.0 * phi2.sin() + 2.0 * phi3.sin() + phi4.sin());
+
+        // Past 90° the wall would fold back, the horn ends there
+        if dz <= 1e-9 * step {
+            break;
+        }
+        // Shorten the last step so the wall ends exactly on the mouth radius or length
+        let fraction = ((mouth_radius - r) / dr).min((max_length - z) / dz).min(1.0);
+        l += step;
+        z += dz * fraction;
+        r += dr * fraction;
+        wall.push((z, r));
+    }
+
+    wall
+}
+
+impl Waveguide for LeCleachWG {
+    fn tilt(&self) -> f64 {
+        self.tilt
+    }
+
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        let mouth = self.mouth_radius.at(theta);
+        Ok((0..resolution)
+            .map(|i| {
+                let z = length * (i as f64) / ((resolution - 1) as f64);
+                ProfilePoint { z, r: self.radial_distance(z, mouth), theta }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ClassicHornWG, HornProfile};
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        assert!(LeCleachWG::new(12.7, 500.0, 0.7, AzimuthalValue::Rectangular { h: 160.0, v: 90.0 }, 0.0).is_ok());
+        assert!(LeCleachWG::new(12.7, 500.0, 0.7, AzimuthalValue::Elliptical { h: 160.0, v: 10.0 }, 0.0).is_err());
+        assert!(LeCleachWG::new(12.7, 500.0, -1.0, AzimuthalValue::Constant(150.0), 0.0).is_err());
+        assert!(LeCleachWG::new(12.7, 0.0, 0.7, AzimuthalValue::Constant(150.0), 0.0).is_err());
+        let record = r#"{"r_init":12.7,"cutoff_frequency":500.0,"t":0.7,"mouth_radius":{"Constant":12.0},"tilt":0.0}"#;
+        assert!(serde_json::from_str::<LeCleachWG>(record).is_err());
+    }
+
+    #[test]
+    fn circular_mouth_matches_the_classic_horn() {
+        let horn = ClassicHornWG::new(12.7, 500.0, 150.0, 0.0, HornProfile::LeCleach { t: 0.7 }).unwrap();
+        let waveguide = LeCleachWG::new(12.7, 500.0, 0.7, AzimuthalValue::Constant(150.0), 0.0).unwrap();
+        assert!((waveguide.length() - horn.length()).abs() < 1e-9 * horn.length());
+        for i in 0..=10 {
+            let z = horn.length() * i as f64 / 10.0;
+            assert!((waveguide.radial_distance(z, 150.0) - horn.radial_distance(z)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn every_azimuth_ends_on_its_mouth() {
+        let mouth = AzimuthalValue::Rectangular { h: 160.0, v: 90.0 };
+        let waveguide = LeCleachWG::new(12.7, 500.0, 0.7, mouth.clone(), 0.0).unwrap();
+        let length = waveguide.length();
+        for theta in [0.0, 0.3, (90.0f64).atan2(160.0), 1.2, PI / 2.0] {
+            let profile = waveguide.generate_profile(length, theta, 41).unwrap();
+            assert!((profile[40].r - mouth.at(theta)).abs() < 1e-6 * mouth.at(theta));
+            assert!(profile.windows(2).all(|pair| pair[1].r >= pair[0].r));
+        }
+
+        // The walls are integrated again from the record
+        let copy: LeCleachWG = serde_json::from_str(&serde_json::to_string(&waveguide).unwrap()).unwrap();
+        assert_eq!(copy.radial_distance(0.5 * length, 120.0), waveguide.radial_distance(0.5 * length, 120.0));
+    }
+}
@@ -1,4 +1,5 @@
 mod oswg;
+mod azimuthal;
 mod ellipsoidal;
 mod axisym;
 mod rectangular_alpha;
@@ -6,8 +7,16 @@ mod rectangular_morph;
 mod oswg_clothoid;
 mod axisym_clothoid;
 mod rect_clothoid;
+mod termination;
+mod waveguide;
+mod spline;
+mod tabulated;
+mod classic_horn;
+mod le_cleach;
+mod sampling;
 
 pub use oswg::OblateSpheroidWG;
+pub use azimuthal::AzimuthalValue;
 pub use ellipsoidal::EllipsoidalOSWG;
 pub use axisym::AxisymOSWG;
 pub use rectangular_alpha::RectangularOSWG;
@@ -16,3 +25,10 @@ pub use rectangular_morph::RectangularMorphOSWG;
 pub use oswg_clothoid::OblateSpheroidClothoidWG;
 pub use axisym_clothoid::AxisymOSCWG;
 pub use rect_clothoid::RectOSCWG;
+pub use termination::{Clothoid, EllipticalArc, Termination, TerminationCurve, TerminationEnd};
+
+pub use waveguide::Waveguide;
+pub use tabulated::TabulatedWG;
+pub use classic_horn::{ClassicHornWG, HornProfile};
+pub use le_cleach::LeCleachWG;
+pub use sampling::AzimuthalSampling;
@@ -1,14 +1,31 @@
-use crate::geometry_types::{CartesianPoint, ProfilePoint};
-use std::f64::consts::PI;
+use crate::geometry_types::ProfilePoint;
+use crate::models::{Termination, TerminationCurve};
+use std::io;
+
+/// Slope dr/dz and curvature at z of the generalized OS profile with these parameters, where
+/// the terminations join it
+pub(crate) fn os_slope_and_curvature(k: f64, r_init: f64, alpha_init: f64, z: f64, tan_alpha: f64) -> (f64, f64) {
+    let b = 2.0 * k * r_init * alpha_init.tan();
+    let f = (k * r_init).powi(2) + b * z + (z * tan_alpha).powi(2);
+    let df = b + 2.0 * z * tan_alpha.powi(2);
+    let d2f = 2.0 * tan_alpha.powi(2);
+
+    let slope = df / (2.0 * f.sqrt());
+    let d2r = (2.0 * f * d2f - df.powi(2)) / (4.0 * f.powf(1.5));
+    (slope, d2r / (1.0 + slope.powi(2)).powf(1.5))
+}
 
 pub trait OblateSpheroidWG {
     // Common parameters
     fn k(&self) -> f64;
     fn r_init(&self) -> f64;
     fn alpha_init(&self) -> f64;
-    fn s(&self) -> f64;
-    fn q(&self) -> f64;
-    fn n(&self) -> f64;
+    fn tilt(&self) -> f64;
+    fn s(&self, theta: f64) -> f64;
+    fn q(&self, theta: f64) -> f64;
+    fn n(&self, theta: f64) -> f64;
+    /// Termination appended after z = L instead of the OS-SE superellipse term
+    fn termination(&self) -> Option<Termination>;
 
     // Common calculations
     fn generalized_os_distance(&self, z: f64, tan_alpha: f64) -> f64 {
@@ -18,9 +35,12 @@ pub trait OblateSpheroidWG {
         (a + b + c).sqrt() + self.r_init() * (1.0 - self.k())
     }
 
-    fn termination_distance(&self, z: f64, l: f64) -> f64 {
-        self.s() * l / self.q()
-            * (1.0 - (1.0 - (z * self.q() / l).powf(self.n())).powf(1.0 / self.n()))
+    fn termination_distance(&self, z: f64, theta: f64, l: f64) -> f64 {
+        if self.termination().is_some() {
+            return 0.0;
+        }
+        let (s, q, n) = (self.s(theta), self.q(theta), self.n(theta));
+        s * l / q * (1.0 - (1.0 - (z * q / l).powf(n)).powf(1.0 / n))
     }
 
     fn morph_function(&self, _theta: f64, _l: f64) -> Option<f64> {
@@ -30,7 +50,7 @@ pub trait OblateSpheroidWG {
     // Angle calculation (to be implemented by variants)
     fn calculate_tan_alpha(&self, theta: f64, l: f64) -> f64 {
         if let Some(val) = self.morph_function(theta, l) {
-            ((val - self.termination_distance(l, l) - self.r_init() * (1.0 - self.k())).powi(2)
+            ((val - self.termination_distance(l, theta, l) - self.r_init() * (1.0 - self.k())).powi(2)
                 - (self.k() * self.r_init()).powi(2)
                 - 2.0 * self.k() * self.r_init() * l * self.alpha_init().tan())
             .sqrt()
@@ -42,12 +62,21 @@ pub trait OblateSpheroidWG {
 
     fn radial_distance(&self, z: f64, theta: f64, l: f64) -> f64 {
         let tan_alpha = self.calculate_tan_alpha(theta, l);
-        self.generalized_os_distance(z, tan_alpha) + self.termination_distance(z, l)
+        self.generalized_os_distance(z, tan_alpha) + self.termination_distance(z, theta, l)
     }
 
-    /// Generate profile points along one angle
-    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> Vec<ProfilePoint> {
-        (0..resolution)
+    /// Termination continuing the OS profile at z = L along one angle. Fails when it cannot be
+    /// solved (see `Termination::solve`).
+    fn termination_curve(&self, termination: &Termination, theta: f64, l: f64) -> io::Result<TerminationCurve> {
+        let tan_alpha = self.calculate_tan_alpha(theta, l);
+        let (slope, curvature) = os_slope_and_curvature(self.k(), self.r_init(), self.alpha_init(), l, tan_alpha);
+        let start = (l, self.radial_distance(l, theta, l));
+        termination.solve(start, slope.atan(), curvature)
+    }
+
+    /// Generate profile points along one angle, failing when its termination cannot be solved
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        let mut profile: Vec<ProfilePoint> = (0..resolution)
             .map(|i| {
                 let z = length * (i as f64) / ((resolution - 1) as f64);
                 ProfilePoint {
@@ -56,55 +85,23 @@ pub trait OblateSpheroidWG {
                     theta,
                 }
             })
-            .collect()
-    }
-
-    /// Generate full 3D mesh
-    fn generate_mesh(
-        &self,
-        length: f64,
-        azimuth_steps: usize,
-        axial_steps: usize,
-    ) -> Vec<[CartesianPoint; 3]> {
-        let theta_positions: Vec<f64> = (0..azimuth_steps)
-            .map(|i| 2.0 * PI * (i as f64) / (azimuth_steps as f64))
             .collect();
 
-        let profiles: Vec<Vec<ProfilePoint>> = theta_positions
-            .iter()
-            .map(|&theta| self.generate_profile(length, theta, axial_steps))
-            .collect();
-
-        let mut triangles = Vec::new();
-
-        for profile_idx in 0..azimuth_steps {
-            let next_profile_idx = (profile_idx + 1) % azimuth_steps;
-
-            let current_profile = &profiles[profile_idx];
-            let next_profile = &profiles[next_profile_idx];
-
-            for point_idx in 0..(axial_steps - 1) {
-                let p0 = current_profile[point_idx];
-                let p1 = current_profile[point_idx + 1];
-                let p2 = next_profile[point_idx];
-                let p3 = next_profile[point_idx + 1];
-
-                // Triangle 1 (p0, p2, p1) - CCW for outward normals
-                triangles.push([
-                    CartesianPoint::from_cylindrical(p0.r, p0.theta, p0.z),
-                    CartesianPoint::from_cylindrical(p2.r, p2.theta, p2.z),
-                    CartesianPoint::from_cylindrical(p1.r, p1.theta, p1.z),
-                ]);
-
-                // Triangle 2 (p1, p2, p3)
-                triangles.push([
-                    CartesianPoint::from_cylindrical(p1.r, p1.theta, p1.z),
-                    CartesianPoint::from_cylindrical(p2.r, p2.theta, p2.z),
-                    CartesianPoint::from_cylindrical(p3.r, p3.theta, p3.z),
-                ]);
-            }
+        if let Some(termination) = self.termination() {
+            // Sampled with the point count of the horizontal profile, the same for every angle
+            let step_length = length / ((resolution - 1) as f64);
+            let horizontal_length = self.termination_curve(&termination, 0.0, length)?.length();
+            let steps = (horizontal_length / step_length).round().max(1.0) as usize;
+
+            let curve = self.termination_curve(&termination, theta, length)?;
+            profile.extend(
+                curve
+                    .sample(steps)
+                    .into_iter()
+                    .map(|(z, r)| ProfilePoint { z, r, theta }),
+            );
         }
 
-        triangles
+        Ok(profile)
     }
 }
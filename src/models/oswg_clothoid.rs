@@ -1,14 +1,23 @@
-use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::geometry_types::ProfilePoint;
+use crate::models::oswg::os_slope_and_curvature;
+use crate::models::{Termination, TerminationCurve};
 use std::f64::consts::PI;
+use std::io;
 
+/// Angles over the turn at which `termination_steps` measures the terminations
+const TERMINATION_ANGLES: usize = 64;
+
+/// Generalized OS waveguide whose profiles continue past L with a `Termination`. Meshing and
+/// exports go through the `Waveguide` impl of each model, which samples the OS part with the
+/// axial resolution and the terminations with `termination_steps` points.
 pub trait OblateSpheroidClothoidWG {
     // Common parameters
     fn k(&self) -> f64;
     fn r_init(&self) -> f64;
     fn alpha_init(&self) -> f64;
+    fn tilt(&self) -> f64;
 
-    fn term_length(&self) -> f64;
-    fn term_end_radius(&self) -> f64;
+    fn termination(&self) -> Termination;
 
     // Common calculations
     fn generalized_os_distance(&self, z: f64, tan_alpha: f64) -> f64 {
@@ -35,98 +44,67 @@ pub trait OblateSpheroidClothoidWG {
         }
     }
 
-    /// Generate profile points along one angle
-    fn generate_profile(&self, length: f64, theta: f64, step_length: f64) -> Vec<ProfilePoint> {
-        // first, calculate the profile for the generalized OS until L
-        let resolution = (length / step_length) as usize + 1; // +1 to include the last point
-        let mut profile: Vec<ProfilePoint> = (0..resolution)
+    /// Generalized OS part of the profile along one angle, `resolution` points until `length`
+    fn generate_os_profile(&self, length: f64, theta: f64, resolution: usize) -> Vec<ProfilePoint> {
+        let tan_alpha = self.calculate_tan_alpha(theta, length);
+        (0..resolution)
             .map(|i| {
-                let tan_alpha = self.calculate_tan_alpha(theta, length);
-                let z = (i as f64) * step_length;
+                let z = length * (i as f64) / ((resolution - 1) as f64);
                 ProfilePoint {
                     z,
                     r: self.generalized_os_distance(z, tan_alpha),
                     theta,
                 }
             })
-            .collect();
-        // then add the termination with clothoid/euler spiral
-        self.add_termination(&mut profile, step_length);
+            .collect()
+    }
 
-        profile
+    /// Termination continuing the OS profile from its last point, with matching wall angle
+    /// (and curvature for the clothoid, G2 junction). Fails when it cannot be solved (see
+    /// `Termination::solve`).
+    fn termination_curve(&self, length: f64, profile: &[ProfilePoint]) -> io::Result<TerminationCurve> {
+        let junction = profile[profile.len() - 1];
+        let tan_alpha = self.calculate_tan_alpha(junction.theta, length);
+        let (slope, curvature) =
+            os_slope_and_curvature(self.k(), self.r_init(), self.alpha_init(), junction.z, tan_alpha);
+        self.termination().solve((junction.z, junction.r), slope.atan(), curvature)
     }
 
-    /// Adds a termination section to the profile using a clothoid (Euler spiral)
-    /// @param profile: The existing profile points to which the termination will be added
-    fn add_termination(&self, profile: &mut Vec<ProfilePoint>, step_length: f64) {
-        let initial_curvature_angle = ((profile[profile.len() - 1].r
-            - profile[profile.len() - 2].r)
-            / (profile[profile.len() - 1].z - profile[profile.len() - 2].z))
-            .atan(); // atan(dy/dx) for the first point
-        for i in 0..(self.term_length() / step_length).round() as i64 {
-            // theta_n = s_n**2 / (2*Rc*sc) + theta_init
-            // => zn+1 = zn + step_length*cos(theta_n)
-            let ultimate_point = profile.last().unwrap();
-            let curvature_angle = initial_curvature_angle
-                + (i as f64 * step_length).powi(2)
-                    / (2.0 * self.term_length() * self.term_end_radius());
-            profile.push(ProfilePoint {
-                z: ultimate_point.z + step_length * (curvature_angle).cos(),
-                r: ultimate_point.r + step_length * (curvature_angle).sin(),
-                theta: ultimate_point.theta,
-            });
+    /// Points sampled on the termination of every angle, so the grid stays regular and a
+    /// profile re-sampled alone matches it: the longest termination over the turn at the step
+    /// length of the OS part
+    fn termination_steps(&self, length: f64, resolution: usize) -> io::Result<usize> {
+        let step_length = length / ((resolution - 1) as f64);
+        let mut steps = 1;
+        for i in 0..TERMINATION_ANGLES {
+            let theta = 2.0 * PI * (i as f64) / (TERMINATION_ANGLES as f64);
+            let termination = self.termination_curve(length, &self.generate_os_profile(length, theta, 2))?;
+            steps = steps.max((termination.length() / step_length).round() as usize);
         }
+        Ok(steps)
     }
 
-    /// Generate full 3D mesh
-    fn generate_mesh(
-        &self,
-        length: f64,
-        azimuth_steps: usize,
-        axial_step_length: f64,
-    ) -> Vec<[CartesianPoint; 3]> {
-        let theta_positions: Vec<f64> = (0..azimuth_steps)
-            .map(|i| 2.0 * PI * (i as f64) / (azimuth_steps as f64))
-            .collect();
-
-        let profiles: Vec<Vec<ProfilePoint>> = theta_positions
-            .iter()
-            .map(|&theta| self.generate_profile(length, theta, axial_step_length))
-            .collect();
-
-        let mut triangles = Vec::new();
-
-        for profile_idx in 0..azimuth_steps {
-            let next_profile_idx = (profile_idx + 1) % azimuth_steps;
-
-            let current_profile = &profiles[profile_idx];
-            let next_profile = &profiles[next_profile_idx];
-
-            let nb_axial_steps = current_profile.len();
-            println!("nb_axial_steps : {}", nb_axial_steps);
-
-            for point_idx in 0..(nb_axial_steps - 1) {
-                let p0 = current_profile[point_idx];
-                let p1 = current_profile[point_idx + 1];
-                let p2 = next_profile[point_idx];
-                let p3 = next_profile[point_idx + 1];
-
-                // Triangle 1 (p0, p2, p1) - CCW for outward normals
-                triangles.push([
-                    CartesianPoint::from_cylindrical(p0.r, p0.theta, p0.z),
-                    CartesianPoint::from_cylindrical(p2.r, p2.theta, p2.z),
-                    CartesianPoint::from_cylindrical(p1.r, p1.theta, p1.z),
-                ]);
+    /// Generate profile points along one angle: `resolution` on the OS part, then
+    /// `termination_steps` on the termination. Fails when a termination cannot be solved.
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        // first, calculate the profile for the generalized OS until L
+        let mut profile = self.generate_os_profile(length, theta, resolution);
+        // then add the termination (clothoid/euler spiral or arc)
+        let termination = self.termination_curve(length, &profile)?;
+        self.add_termination(&mut profile, &termination, self.termination_steps(length, resolution)?);
 
-                // Triangle 2 (p1, p2, p3)
-                triangles.push([
-                    CartesianPoint::from_cylindrical(p1.r, p1.theta, p1.z),
-                    CartesianPoint::from_cylindrical(p2.r, p2.theta, p2.z),
-                    CartesianPoint::from_cylindrical(p3.r, p3.theta, p3.z),
-                ]);
-            }
-        }
+        Ok(profile)
+    }
 
-        triangles
+    /// Adds a termination section to the profile, sampling the curve with `steps` points
+    /// @param profile: The existing profile points to which the termination will be added
+    fn add_termination(&self, profile: &mut Vec<ProfilePoint>, termination: &TerminationCurve, steps: usize) {
+        let theta = profile[profile.len() - 1].theta;
+        profile.extend(
+            termination
+                .sample(steps)
+                .into_iter()
+                .map(|(z, r)| ProfilePoint { z, r, theta }),
+        );
     }
 }
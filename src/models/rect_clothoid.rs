@@ -1,11 +1,16 @@
-use crate::models::OblateSpheroidClothoidWG;
+use crate::geometry_types::ProfilePoint;
+use crate::models::{OblateSpheroidClothoidWG, Termination, Waveguide};
+use crate::throat::ThroatCap;
+use std::io;
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RectOSCWG {
     pub k: f64,
     pub r_init: f64,
     pub alpha_init: f64,
-    pub term_end_radius: f64,
-    pub term_length: f64,
+    pub tilt: f64,
+    pub termination: Termination,
     pub alpha_h: f64,
     pub alpha_v: f64,
 }
@@ -20,11 +25,11 @@ impl OblateSpheroidClothoidWG for RectOSCWG {
     fn alpha_init(&self) -> f64 {
         self.alpha_init
     }
-    fn term_length(&self) -> f64 {
-        self.term_length
+    fn tilt(&self) -> f64 {
+        self.tilt
     }
-    fn term_end_radius(&self) -> f64 {
-        self.term_end_radius
+    fn termination(&self) -> Termination {
+        self.termination
     }
 
     fn calculate_tan_alpha(&self, theta: f64, _l:f64) -> f64 {
@@ -34,3 +39,57 @@ impl OblateSpheroidClothoidWG for RectOSCWG {
         (h_axis/theta.cos().abs()).min(v_axis/theta.sin().abs()) // simplified l in tan(alpha) and tan(h_axis)
     }
 }
+
+impl Waveguide for RectOSCWG {
+    fn tilt(&self) -> f64 {
+        self.tilt
+    }
+
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        OblateSpheroidClothoidWG::generate_profile(self, length, theta, resolution)
+    }
+
+    fn throat_cap(&self) -> ThroatCap {
+        ThroatCap::wavefront(self.r_init, self.alpha_init)
+    }
+
+    fn lip_thickness(&self) -> Option<f64> {
+        self.termination.lip_thickness()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::Symmetry;
+    use crate::models::TerminationEnd;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn resampled_profiles_match_the_grid() {
+        // The wall angle at the junction varies with θ, and so does the termination length
+        let waveguide = RectOSCWG {
+            k: 1.0,
+            r_init: 12.7,
+            alpha_init: 0.0,
+            tilt: 0.0,
+            termination: Termination::Clothoid(TerminationEnd::WallAngle { angle: PI / 2.0, end_radius: 20.0 }),
+            alpha_h: 0.8,
+            alpha_v: 0.4,
+        };
+        let termination_length = |theta: f64| {
+            let os = waveguide.generate_os_profile(100.0, theta, 2);
+            waveguide.termination_curve(100.0, &os).unwrap().length()
+        };
+        assert!((termination_length(0.0) - termination_length(PI / 2.0)).abs() > 10.0);
+
+        let profiles = waveguide.generate_sector_profiles(100.0, 16, 21, Symmetry::Full).unwrap();
+        for profile in &profiles {
+            let single = Waveguide::generate_profile(&waveguide, 100.0, profile[0].theta, 21).unwrap();
+            assert_eq!(single.len(), profile.len());
+            for (a, b) in profile.iter().zip(&single) {
+                assert!((a.z - b.z).abs() < 1e-12 && (a.r - b.r).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::mesh::Symmetry;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::io;
+
+/// Placement of the profile angles around the axis, the same at every z
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum AzimuthalSampling {
+    /// Evenly spaced in θ
+    Uniform(usize),
+    /// Evenly spaced in arc length along the mouth contour, starting at the sector start
+    MouthArcLength(usize),
+}
+
+impl AzimuthalSampling {
+    pub fn steps(&self) -> usize {
+        match *self {
+            AzimuthalSampling::Uniform(steps) | AzimuthalSampling::MouthArcLength(steps) => steps,
+        }
+    }
+
+    /// Profile angles covering the `symmetry` sector, with its share of the steps; `profile`
+    /// gives the profile at an angle, its last point being on the mouth, and its errors are
+    /// returned. Sector edges are sampled exactly.
+    pub fn positions(&self, symmetry: Symmetry, profile: impl Fn(f64) -> io::Result<Vec<ProfilePoint>>) -> io::Result<Vec<f64>> {
+        let (start, sector) = (symmetry.start(), symmetry.sector());
+        let intervals = ((self.steps() as f64 * sector / (2.0 * PI)).round() as usize).max(1);
+        // The full turn does not repeat θ = 2π
+        let count = if symmetry == Symmetry::Full { intervals } else { intervals + 1 };
+
+        match *self {
+            AzimuthalSampling::Uniform(_) => Ok((0..count)
+                .map(|i| start + sector * (i as f64) / (intervals as f64))
+                .collect()),
+            AzimuthalSampling::MouthArcLength(_) => {
+                // Dense uniform pass to measure the contour, then invert its cumulative length
+                let dense: Vec<f64> = (0..=8 * intervals)
+                    .map(|i| start + sector * (i as f64) / ((8 * intervals) as f64))
+                    .collect();
+                let mouth: Vec<CartesianPoint> = dense
+                    .iter()
+                    .map(|&theta| {
+                        let points = profile(theta)?;
+                        let end = points[points.len() - 1];
+                        Ok(CartesianPoint::from_cylindrical(end.r, end.theta, end.z))
+                    })
+                    .collect::<io::Result<_>>()?;
+                let mut arc_length = vec![0.0];
+                for pair in mouth.windows(2) {
+                    arc_length.push(arc_length[arc_length.len() - 1] + (pair[1] - pair[0]).norm());
+                }
+
+                let total = arc_length[arc_length.len() - 1];
+                let mut segment = 0;
+                Ok((0..count)
+                    .map(|i| {
+                        if i == intervals {
+                            return start + sector;
+                        }
+                        let target = total * i as f64 / intervals as f64;
+                        while segment + 2 < arc_length.len() && arc_length[segment + 1] < target {
+                            segment += 1;
+                        }
+                        let span = arc_length[segment + 1] - arc_length[segment];
+                        let t = if span > 0.0 { ((target - arc_length[segment]) / span).min(1.0) } else { 0.0 };
+                        dense[segment] + t * (dense[segment + 1] - dense[segment])
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Profiles at the `positions` covering the `symmetry` sector
+    pub fn sample(
+        &self,
+        symmetry: Symmetry,
+        profile: impl Fn(f64) -> io::Result<Vec<ProfilePoint>>,
+    ) -> io::Result<Vec<Vec<ProfilePoint>>> {
+        self.positions(symmetry, &profile)?.into_iter().map(profile).collect()
+    }
+}
+
+impl From<usize> for AzimuthalSampling {
+    fn from(steps: usize) -> Self {
+        AzimuthalSampling::Uniform(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Spread between the longest and shortest mouth chords, relative to the shortest
+    fn chord_spread(thetas: &[f64], mouth: impl Fn(f64) -> CartesianPoint) -> f64 {
+        let chords: Vec<f64> = thetas.windows(2).map(|pair| (mouth(pair[1]) - mouth(pair[0])).norm()).collect();
+        let shortest = chords.iter().copied().fold(f64::INFINITY, f64::min);
+        chords.iter().copied().fold(0.0, f64::max) / shortest - 1.0
+    }
+
+    #[test]
+    fn mouth_arc_length_spaces_the_mouth_evenly() {
+        // Elliptical mouth, 150 by 60
+        let radius = |theta: f64| 150.0 * 60.0 / (60.0 * theta.cos()).hypot(150.0 * theta.sin());
+        let profile = |theta: f64| Ok(vec![ProfilePoint { z: 0.0, r: 10.0, theta }, ProfilePoint { z: 100.0, r: radius(theta), theta }]);
+        let mouth = |theta: f64| CartesianPoint::from_cylindrical(radius(theta), theta, 100.0);
+
+        let mut thetas = AzimuthalSampling::MouthArcLength(64).positions(Symmetry::Full, profile).unwrap();
+        assert_eq!(thetas.len(), 64);
+        thetas.push(thetas[0] + 2.0 * PI);
+        assert!(chord_spread(&thetas, mouth) < 0.02);
+        let mut uniform = AzimuthalSampling::Uniform(64).positions(Symmetry::Full, profile).unwrap();
+        uniform.push(2.0 * PI);
+        assert!(chord_spread(&uniform, mouth) > 0.5);
+
+        // Sector edges exactly, with the sector share of the steps
+        let quarter = AzimuthalSampling::MouthArcLength(64).positions(Symmetry::Quarter, profile).unwrap();
+        assert_eq!(quarter.len(), 17);
+        assert_eq!((quarter[0], quarter[16]), (Symmetry::Quarter.start(), Symmetry::Quarter.start() + PI / 2.0));
+        assert!(chord_spread(&quarter, mouth) < 0.02);
+
+        let failing = |_: f64| Err(io::Error::new(io::ErrorKind::InvalidInput, "no profile"));
+        assert!(AzimuthalSampling::MouthArcLength(64).positions(Symmetry::Full, failing).is_err());
+    }
+}
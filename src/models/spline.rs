@@ -0,0 +1,102 @@
+/// Monotone cubic Hermite interpolation (Fritsch-Carlson), preserves the monotonicity of the
+/// data so tabulated profiles do not overshoot between samples
+#[derive(Debug, Clone)]
+pub struct MonotoneCubic {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    slopes: Vec<f64>,
+}
+
+impl MonotoneCubic {
+    /// `xs` must be strictly increasing and have the same length as `ys`
+    pub fn new(xs: Vec<f64>, ys: Vec<f64>) -> Self {
+        let n = xs.len();
+        let secants: Vec<f64> = (0..n.saturating_sub(1))
+            .map(|i| (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]))
+            .collect();
+
+        let mut slopes = vec![0.0; n];
+        if n > 1 {
+            slopes[0] = secants[0];
+            slopes[n - 1] = secants[n - 2];
+        }
+        for i in 1..n.saturating_sub(1) {
+            if secants[i - 1] * secants[i] > 0.0 {
+                slopes[i] = (secants[i - 1] + secants[i]) / 2.0;
+            }
+        }
+
+        // Limit the slopes so each interval stays monotone
+        for i in 0..secants.len() {
+            if secants[i] == 0.0 {
+                slopes[i] = 0.0;
+                slopes[i + 1] = 0.0;
+                continue;
+            }
+            let a = slopes[i] / secants[i];
+            let b = slopes[i + 1] / secants[i];
+            let norm = a.hypot(b);
+            if norm > 3.0 {
+                slopes[i] = 3.0 / norm * a * secants[i];
+                slopes[i + 1] = 3.0 / norm * b * secants[i];
+            }
+        }
+
+        Self { xs, ys, slopes }
+    }
+
+    /// Interpolated (x, y) data
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        self.xs.iter().copied().zip(self.ys.iter().copied()).collect()
+    }
+
+    /// Last interpolated (x, y) point
+    pub fn end(&self) -> (f64, f64) {
+        (self.xs[self.xs.len() - 1], self.ys[self.ys.len() - 1])
+    }
+
+    /// Evaluates the spline, clamping `x` to the tabulated range
+    pub fn eval(&self, x: f64) -> f64 {
+        let n = self.xs.len();
+        if n == 1 || x <= self.xs[0] {
+            return self.ys[0];
+        }
+        if x >= self.xs[n - 1] {
+            return self.ys[n - 1];
+        }
+
+        let i = self.xs.partition_point(|&xi| xi <= x) - 1;
+        let h = self.xs[i + 1] - self.xs[i];
+        let t = (x - self.xs[i]) / h;
+        let (t2, t3) = (t * t, t * t * t);
+
+        (2.0 * t3 - 3.0 * t2 + 1.0) * self.ys[i]
+            + (t3 - 2.0 * t2 + t) * h * self.slopes[i]
+            + (-2.0 * t3 + 3.0 * t2) * self.ys[i + 1]
+            + (t3 - t2) * h * self.slopes[i + 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_through_the_data() {
+        let spline = MonotoneCubic::new(vec![0.0, 1.0, 3.0, 4.0], vec![1.0, 2.0, 2.5, 5.0]);
+        for (x, y) in spline.points() {
+            assert!((spline.eval(x) - y).abs() < 1e-12);
+        }
+        assert_eq!(spline.eval(-1.0), 1.0);
+        assert_eq!(spline.eval(10.0), 5.0);
+    }
+
+    #[test]
+    fn preserves_monotonicity() {
+        // A plateau followed by a steep rise makes an unconstrained cubic overshoot
+        let spline = MonotoneCubic::new(vec![0.0, 1.0, 2.0, 3.0], vec![0.0, 0.0, 0.1, 10.0]);
+        let values: Vec<f64> = (0..=300).map(|k| spline.eval(k as f64 / 100.0)).collect();
+        assert!(values.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!(values.iter().take(101).all(|&value| value == 0.0));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::geometry_types::ProfilePoint;
+use crate::mesh::Symmetry;
+use crate::models::spline::MonotoneCubic;
+use crate::models::{AzimuthalSampling, Waveguide};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::io::{self, Error, ErrorKind};
+
+/// Waveguide defined by tabulated (z, r) generatrices, one per angle or a single axisymmetric one.
+/// Profiles are interpolated with monotone cubic splines in z, then in θ (wrapping around 2π).
+/// Serialized as its tables, see `TabulatedTables`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "TabulatedTables", try_from = "TabulatedTables")]
+pub struct TabulatedWG {
+    pub tilt: f64,
+    thetas: Vec<f64>,
+    profiles: Vec<MonotoneCubic>,
+    length: f64,
+}
+
+/// Serialized form of `TabulatedWG`: the tables it was built from, θ in radians
+#[derive(Serialize, Deserialize)]
+struct TabulatedTables {
+    tilt: f64,
+    profiles: Vec<(f64, Vec<(f64, f64)>)>,
+}
+
+impl From<TabulatedWG> for TabulatedTables {
+    fn from(waveguide: TabulatedWG) -> Self {
+        Self {
+            tilt: waveguide.tilt,
+            profiles: waveguide
+                .thetas
+                .iter()
+                .zip(&waveguide.profiles)
+                .map(|(&theta, profile)| (theta, profile.points()))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<TabulatedTables> for TabulatedWG {
+    type Error = Error;
+
+    fn try_from(tables: TabulatedTables) -> std::io::Result<Self> {
+        Ok(Self {
+            tilt: tables.tilt,
+            ..Self::from_profiles(tables.profiles)?
+        })
+    }
+}
+
+impl TabulatedWG {
+    /// Builds the model from (θ, [(z, r)]) tables, θ in radians
+    pub fn from_profiles(mut tables: Vec<(f64, Vec<(f64, f64)>)>) -> std::io::Result<Self> {
+        if tables.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "no profile in table"));
+        }
+        for (theta, _) in tables.iter_mut() {
+            *theta = theta.rem_euclid(2.0 * PI);
+        }
+        tables.sort_by(|a, b| a.0.total_cmp(&b.0));
+        // 0 and 2π are the same angle once wrapped, so the last table also neighbours the first
+        let duplicate = (0..tables.len()).find(|&k| {
+            let next = tables.get(k + 1).map_or(tables[0].0 + 2.0 * PI, |table| table.0);
+            tables.len() > 1 && next - tables[k].0 < 1e-12
+        });
+        if let Some(k) = duplicate {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("two profiles at theta = {}", tables[k].0),
+            ));
+        }
+
+        let mut length = f64::INFINITY;
+        let mut thetas = Vec::new();
+        let mut profiles = Vec::new();
+        for (theta, points) in tables {
+            if points.len() < 2 || points.windows(2).any(|pair| pair[1].0 <= pair[0].0) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("profile at theta = {theta} must have strictly increasing z"),
+                ));
+            }
+            length = length.min(points[points.len() - 1].0);
+            thetas.push(theta);
+            profiles.push(MonotoneCubic::new(
+                points.iter().map(|p| p.0).collect(),
+                points.iter().map(|p| p.1).collect(),
+            ));
+        }
+
+        Ok(Self {
+            tilt: 0.0,
+            thetas,
+            profiles,
+            length,
+        })
+    }
+
+    /// Loads the tables from a CSV with the columns written by the profile exporter
+    /// (z, r, theta, ...); rows are grouped by theta, lines starting with `#` are skipped
+    pub fn from_csv(filename: &str) -> std::io::Result<Self> {
+        #[derive(Deserialize)]
+        struct CsvPoint {
+            z: f64,
+            r: f64,
+            theta: f64,
+        }
+
+        let mut tables: Vec<(f64, Vec<(f64, f64)>)> = Vec::new();
+        let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_path(filename)?;
+        for row in reader.deserialize() {
+            let point: CsvPoint = row?;
+            match tables.iter_mut().find(|(theta, _)| *theta == point.theta) {
+                Some((_, points)) => points.push((point.z, point.r)),
+                None => tables.push((point.theta, vec![(point.z, point.r)])),
+            }
+        }
+
+        Self::from_profiles(tables)
+    }
+
+    /// Axial length covered by every tabulated profile
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    pub fn radial_distance(&self, z: f64, theta: f64) -> f64 {
+        self.angular_spline(z).eval(theta.rem_euclid(2.0 * PI))
+    }
+
+    /// Spline of the radius over θ in [0, 2π) at `z`, built once and evaluated at every angle
+    fn angular_spline(&self, z: f64) -> MonotoneCubic {
+        let radii: Vec<f64> = self.profiles.iter().map(|profile| profile.eval(z)).collect();
+        let n = radii.len();
+        if n == 1 {
+            return MonotoneCubic::new(self.thetas.clone(), radii);
+        }
+
+        // Extend the angles by two samples on each side so the spline is periodic over [0, 2π)
+        let mut thetas = Vec::with_capacity(n + 4);
+        let mut values = Vec::with_capacity(n + 4);
+        for i in [n - 2, n - 1] {
+            thetas.push(self.thetas[i] - 2.0 * PI);
+            values.push(radii[i]);
+        }
+        thetas.extend_from_slice(&self.thetas);
+        values.extend_from_slice(&radii);
+        for i in [0, 1] {
+            thetas.push(self.thetas[i] + 2.0 * PI);
+            values.push(radii[i]);
+        }
+
+        MonotoneCubic::new(thetas, values)
+    }
+}
+
+impl Waveguide for TabulatedWG {
+    fn tilt(&self) -> f64 {
+        self.tilt
+    }
+
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        Ok((0..resolution)
+            .map(|i| {
+                let z = length * (i as f64) / ((resolution - 1) as f64);
+                ProfilePoint {
+                    z,
+                    r: self.radial_distance(z, theta),
+                    theta,
+                }
+            })
+            .collect())
+    }
+
+    /// Same profiles as `generate_profile` at every angle, with one θ spline per z
+    fn sample_profiles(
+        &self,
+        length: f64,
+        azimuth: AzimuthalSampling,
+        axial_steps: usize,
+        symmetry: Symmetry,
+    ) -> io::Result<Vec<Vec<ProfilePoint>>> {
+        let thetas = azimuth.positions(symmetry, |theta| self.generate_profile(length, theta, axial_steps))?;
+        let mut profiles = vec![Vec::with_capacity(axial_steps); thetas.len()];
+        for i in 0..axial_steps {
+            let z = length * (i as f64) / ((axial_steps - 1) as f64);
+            let spline = self.angular_spline(z);
+            for (profile, &theta) in profiles.iter_mut().zip(&thetas) {
+                profile.push(ProfilePoint {
+                    z,
+                    r: spline.eval(theta.rem_euclid(2.0 * PI)),
+                    theta,
+                });
+            }
+        }
+        Ok(profiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cone(slope: f64) -> Vec<(f64, f64)> {
+        vec![(0.0, 10.0), (50.0, 10.0 + 50.0 * slope), (100.0, 10.0 + 100.0 * slope)]
+    }
+
+    #[test]
+    fn duplicate_angles_are_rejected() {
+        assert!(TabulatedWG::from_profiles(vec![(0.0, cone(0.5)), (2.0 * PI, cone(1.0))]).is_err());
+        assert!(TabulatedWG::from_profiles(vec![(1.0, cone(0.5)), (1.0, cone(1.0))]).is_err());
+    }
+
+    #[test]
+    fn sector_profiles_match_single_profiles() {
+        let tables = vec![(0.0, cone(1.0)), (PI / 2.0, cone(0.5)), (PI, cone(1.0)), (1.5 * PI, cone(0.5))];
+        let waveguide = TabulatedWG::from_profiles(tables).unwrap();
+        let profiles = waveguide.generate_profiles(100.0, 12, 11).unwrap();
+        for profile in &profiles {
+            let single = waveguide.generate_profile(100.0, profile[0].theta, 11).unwrap();
+            for (a, b) in profile.iter().zip(&single) {
+                assert!((a.r - b.r).abs() < 1e-12);
+            }
+        }
+        assert!((waveguide.radial_distance(100.0, PI / 2.0) - 60.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sectors_need_matching_mirror_profiles() {
+        let symmetric = vec![(0.0, cone(1.0)), (PI / 2.0, cone(0.5)), (PI, cone(1.0)), (1.5 * PI, cone(0.5))];
+        let waveguide = TabulatedWG::from_profiles(symmetric).unwrap();
+        assert!(waveguide.generate_sector_profiles(100.0, 12, 11, Symmetry::Quarter).is_ok());
+
+        // Wider towards +y than towards -y: no mirror plane in y = 0
+        let lopsided = vec![(0.0, cone(1.0)), (PI / 2.0, cone(0.5)), (PI, cone(1.0)), (1.5 * PI, cone(0.8))];
+        let waveguide = TabulatedWG::from_profiles(lopsided).unwrap();
+        assert!(waveguide.generate_sector_profiles(100.0, 12, 11, Symmetry::Full).is_ok());
+        assert!(waveguide.generate_sector_profiles(100.0, 12, 11, Symmetry::Half).is_err());
+        assert!(waveguide.generate_sector_mesh(100.0, 12, 11, Symmetry::Quarter).is_err());
+    }
+
+    #[test]
+    fn tilted_sector_keeps_the_x_mirror_plane() {
+        let tables = vec![(0.0, cone(1.0)), (PI / 2.0, cone(0.5)), (PI, cone(1.0)), (1.5 * PI, cone(0.5))];
+        let waveguide = TabulatedWG { tilt: 0.2, ..TabulatedWG::from_profiles(tables).unwrap() };
+        let mesh = waveguide.generate_sector_mesh(100.0, 12, 11, Symmetry::Quarter).unwrap();
+        assert_eq!(mesh.symmetry, Symmetry::HalfX);
+        assert!(mesh.vertices.iter().all(|vertex| vertex.x >= 0.0));
+        let on_plane = mesh.vertices.iter().filter(|vertex| vertex.x == 0.0).count();
+        assert_eq!(on_plane, 2 * 11);
+    }
+}
@@ -0,0 +1,465 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::io::{Error, ErrorKind};
+
+/// Mouth termination continuing the OS profile tangentially from its last point
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Termination {
+    /// Euler spiral starting with the OS curvature (G2 junction)
+    Clothoid(TerminationEnd),
+    /// Circular arc of `radius` rolling back until the wall is at `angle` from the axis (can exceed π)
+    CircularArc { radius: f64, angle: f64 },
+    /// Elliptical arc with half-axis `a` along the junction tangent and `b` along its normal,
+    /// rolling back until the wall is at `angle` from the axis (can exceed π)
+    EllipticalArc { a: f64, b: f64, angle: f64 },
+    /// Circular lip of `radius` wrapping back past 180° until it meets the outer wall of a
+    /// shell of `thickness` (see `generate_solid`), giving a closed toroidal mouth edge
+    Lip { radius: f64, thickness: f64 },
+}
+
+impl Termination {
+    /// Wall thickness the lip closes onto, for a `Termination::Lip`
+    pub fn lip_thickness(&self) -> Option<f64> {
+        match *self {
+            Termination::Lip { thickness, .. } => Some(thickness),
+            _ => None,
+        }
+    }
+
+    /// Solves the termination for a profile ending at `start` with the given wall angle and
+    /// curvature. Fails when a clothoid cannot reach its end condition (see `Clothoid::solve`).
+    pub fn solve(
+        &self,
+        start: (f64, f64),
+        start_angle: f64,
+        start_curvature: f64,
+    ) -> std::io::Result<TerminationCurve> {
+        Ok(match *self {
+            Termination::Clothoid(end) => {
+                TerminationCurve::Clothoid(Clothoid::solve(start, start_angle, start_curvature, &end)?)
+            }
+            Termination::CircularArc { radius, angle } => TerminationCurve::EllipticalArc(
+                EllipticalArc::new(start, start_angle, radius, radius, angle),
+            ),
+            Termination::EllipticalArc { a, b, angle } => {
+                TerminationCurve::EllipticalArc(EllipticalArc::new(start, start_angle, a, b, angle))
+            }
+            Termination::Lip { radius, thickness } => {
+                // The outer wall lies `thickness` away along the junction normal, the circle
+                // crosses it again after turning by 2π - acos(1 - thickness / radius)
+                let turn = if 2.0 * radius > thickness {
+                    2.0 * PI - (1.0 - thickness / radius).acos()
+                } else {
+                    PI
+                };
+                TerminationCurve::EllipticalArc(EllipticalArc::new(
+                    start,
+                    start_angle,
+                    radius,
+                    radius,
+                    start_angle + turn,
+                ))
+            }
+        })
+    }
+}
+
+/// Termination curve solved for one profile
+#[derive(Debug, Clone, Copy)]
+pub enum TerminationCurve {
+    Clothoid(Clothoid),
+    EllipticalArc(EllipticalArc),
+}
+
+impl TerminationCurve {
+    /// Arc length of the curve
+    pub fn length(&self) -> f64 {
+        match self {
+            TerminationCurve::Clothoid(clothoid) => clothoid.length,
+            TerminationCurve::EllipticalArc(arc) => arc.length(),
+        }
+    }
+
+    /// `steps` points along the curve, excluding the start point
+    pub fn sample(&self, steps: usize) -> Vec<(f64, f64)> {
+        match self {
+            TerminationCurve::Clothoid(clothoid) => clothoid.sample(steps),
+            TerminationCurve::EllipticalArc(arc) => arc.sample(steps),
+        }
+    }
+}
+
+/// End condition of a clothoid termination. The spiral always starts with the wall angle and
+/// curvature of the OS profile at the junction, and ends with the curvature 1 / `end_radius`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum TerminationEnd {
+    /// Spiral of the given arc length
+    Length { length: f64, end_radius: f64 },
+    /// Spiral ending with the wall at `angle` from the axis (π/2 rolls back parallel to the mouth plane)
+    WallAngle { angle: f64, end_radius: f64 },
+    /// Spiral ending at the mouth radius `radius`
+    MouthRadius { radius: f64, end_radius: f64 },
+    /// Spiral ending in the plane at axial position `z` with the wall at `angle` from the axis.
+    /// Length and end curvature are solved for each angle, so every profile of a
+    /// non-axisymmetric model ends in the same flat mouth plane.
+    MouthPlane { z: f64, angle: f64 },
+}
+
+/// Lengths scanned by `first_crossing`, and how far: this many times the distance to cover
+const SCAN_STEPS: usize = 1024;
+const SCAN_LIMIT: f64 = 64.0;
+
+/// Shortest length up to `limit` at which `end(length)` reaches `target`: scanned in
+/// `SCAN_STEPS` steps for the first crossing, then bisected, as `end` need not be monotonic
+/// (a spiral can reach past the target and curl back)
+fn first_crossing(end: impl Fn(f64) -> f64, target: f64, limit: f64) -> Option<f64> {
+    let step = limit / SCAN_STEPS as f64;
+    let mut low = 0.0;
+    for k in 1..=SCAN_STEPS {
+        let mut high = step * k as f64;
+        if end(high) >= target {
+            for _ in 0..60 {
+                let mid = (low + high) / 2.0;
+                if end(mid) >= target {
+                    high = mid;
+                } else {
+                    low = mid;
+                }
+            }
+            return Some((low + high) / 2.0);
+        }
+        low = high;
+    }
+    None
+}
+
+/// Euler spiral in the (z, r) plane, its curvature varying linearly along the arc length
+#[derive(Debug, Clone, Copy)]
+pub struct Clothoid {
+    pub start: (f64, f64), // (z, r)
+    pub start_angle: f64,
+    pub start_curvature: f64,
+    pub end_curvature: f64,
+    pub length: f64,
+}
+
+impl Clothoid {
+    /// Solves the spiral length (and end curvature for `MouthPlane`) so that it satisfies `end`,
+    /// the shortest one when several do. Fails when the spiral, curling up towards its end,
+    /// never reaches the mouth radius, when the mouth plane does not lie ahead of the junction,
+    /// or when the end wall angle would take a negative length.
+    pub fn solve(
+        start: (f64, f64),
+        start_angle: f64,
+        start_curvature: f64,
+        end: &TerminationEnd,
+    ) -> std::io::Result<Self> {
+        let spiral = |length: f64, end_radius: f64| Clothoid {
+            start,
+            start_angle,
+            start_curvature,
+            end_curvature: 1.0 / end_radius,
+            length,
+        };
+
+        let unreachable = |message: String| Err(Error::new(ErrorKind::InvalidInput, message));
+
+        match *end {
+            TerminationEnd::Length { length, end_radius } => Ok(spiral(length, end_radius)),
+            TerminationEnd::WallAngle { angle, end_radius } => {
+                // The wall angle grows by the mean curvature times the length
+                let mean_curvature = (start_curvature + 1.0 / end_radius) / 2.0;
+                let length = (angle - start_angle) / mean_curvature;
+                if length.is_nan() || length < 0.0 {
+                    return unreachable(format!(
+                        "clothoid from the wall angle {} with end radius {} never reaches the wall angle {}",
+                        start_angle, end_radius, angle
+                    ));
+                }
+                Ok(spiral(length, end_radius))
+            }
+            TerminationEnd::MouthRadius { radius, end_radius } => {
+                let end_r = |length: f64| spiral(length, end_radius).end().1;
+                let limit = (radius - start.1).max(end_radius).max(1e-3) * SCAN_LIMIT;
+                match first_crossing(end_r, radius, limit) {
+                    Some(length) => Ok(spiral(length, end_radius)),
+                    None => unreachable(format!(
+                        "clothoid from r = {} never reaches the mouth radius {}",
+                        start.1, radius
+                    )),
+                }
+            }
+            TerminationEnd::MouthPlane { z, angle } => {
+                // For a given length, the end curvature giving the final wall angle follows from
+                // the mean curvature; longer spirals reach further in z
+                if z.is_nan() || z <= start.0 {
+                    return unreachable(format!("mouth plane z = {} is not past the junction z = {}", z, start.0));
+                }
+                let turn = angle - start_angle;
+                let spiral_of_length = |length: f64| Clothoid {
+                    start,
+                    start_angle,
+                    start_curvature,
+                    end_curvature: 2.0 * turn / length - start_curvature,
+                    length,
+                };
+                let end_z = |length: f64| spiral_of_length(length).end().0;
+                match first_crossing(end_z, z, (z - start.0).max(1e-3) * SCAN_LIMIT) {
+                    Some(length) => Ok(spiral_of_length(length)),
+                    None => unreachable(format!("clothoid from z = {} never reaches the mouth plane z = {}", start.0, z)),
+                }
+            }
+        }
+    }
+
+    fn curvature_rate(&self) -> f64 {
+        if self.length > 0.0 {
+            (self.end_curvature - self.start_curvature) / self.length
+        } else {
+            0.0
+        }
+    }
+
+    /// Wall angle at arc length `s`
+    pub fn angle_at(&self, s: f64) -> f64 {
+        self.start_angle + self.start_curvature * s + self.curvature_rate() * s.powi(2) / 2.0
+    }
+
+    /// (z, r) point at arc length `s`
+    pub fn point_at(&self, s: f64) -> (f64, f64) {
+        let (dz, dr) = phase_integral(self.start_angle, self.start_curvature, self.curvature_rate(), s);
+        (self.start.0 + dz, self.start.1 + dr)
+    }
+
+    pub fn end(&self) -> (f64, f64) {
+        self.point_at(self.length)
+    }
+
+    /// `steps` points evenly spaced along the spiral, excluding the start point
+    pub fn sample(&self, steps: usize) -> Vec<(f64, f64)> {
+        (1..=steps)
+            .map(|i| self.point_at(self.length * (i as f64) / (steps as f64)))
+            .collect()
+    }
+}
+
+/// ∫₀ˢ exp(i (a + b u + c u² / 2)) du as (real, imaginary), through the Fresnel integrals
+fn phase_integral(a: f64, b: f64, c: f64, s: f64) -> (f64, f64) {
+    if c.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            return (s * a.cos(), s * a.sin());
+        }
+        return (
+            ((a + b * s).sin() - a.sin()) / b,
+            (a.cos() - (a + b * s).cos()) / b,
+        );
+    }
+    if c < 0.0 {
+        // Conjugate of the integral of the negated phase
+        let (re, im) = phase_integral(-a, -b, -c, s);
+        return (re, -im);
+    }
+
+    // Complete the square: a + b u + c u² / 2 = a - b² / 2c + π/2 t², t = (u + b/c) √(c/π)
+    let scale = (c / PI).sqrt();
+    let (c0, s0) = fresnel((b / c) * scale);
+    let (c1, s1) = fresnel((s + b / c) * scale);
+    let (dc, ds) = ((c1 - c0) / scale, (s1 - s0) / scale);
+    let phase = a - b.powi(2) / (2.0 * c);
+
+    (
+        phase.cos() * dc - phase.sin() * ds,
+        phase.sin() * dc + phase.cos() * ds,
+    )
+}
+
+/// Fresnel integrals (C(x), S(x)) = ∫₀ˣ (cos(π t²/2), sin(π t²/2)) dt
+pub fn fresnel(x: f64) -> (f64, f64) {
+    if x < 0.0 {
+        let (c, s) = fresnel(-x);
+        return (-c, -s);
+    }
+
+    if x < 3.5 {
+        // Power series, alternating terms of (π x² / 2)^n / n!
+        let u = PI * x.powi(2) / 2.0;
+        let (mut c, mut s) = (0.0, 0.0);
+        let mut term = x; // x u^n / n!
+        for n in 0..100 {
+            let contribution = term / (2 * n + 1) as f64;
+            match n % 4 {
+                0 => c += contribution,
+                1 => s += contribution,
+                2 => c -= contribution,
+                _ => s -= contribution,
+            }
+            term *= u / (n + 1) as f64;
+            if term < 1e-17 {
+                break;
+            }
+        }
+        return (c, s);
+    }
+
+    // Asymptotic expansion with the auxiliary functions f and g
+    let v = 1.0 / (PI * x.powi(2)).powi(2);
+    let (mut f, mut g) = (0.0, 0.0);
+    let (mut f_term, mut g_term) = (1.0, 1.0);
+    for n in 0..8 {
+        f += f_term;
+        g += g_term;
+        let n = n as f64;
+        f_term *= -(4.0 * n + 1.0) * (4.0 * n + 3.0) * v;
+        g_term *= -(4.0 * n + 3.0) * (4.0 * n + 5.0) * v;
+    }
+    f /= PI * x;
+    g /= PI.powi(2) * x.powi(3);
+
+    let phase = PI * x.powi(2) / 2.0;
+    (
+        0.5 + f * phase.sin() - g * phase.cos(),
+        0.5 - f * phase.cos() - g * phase.sin(),
+    )
+}
+
+/// Elliptical arc in the (z, r) plane, tangent to the profile at its start and turning towards
+/// increasing wall angles, possibly past 180° so z is not monotonic. A circular arc is the case `a == b`.
+#[derive(Debug, Clone, Copy)]
+pub struct EllipticalArc {
+    pub start: (f64, f64), // (z, r)
+    pub start_angle: f64,
+    pub a: f64, // half-axis along the junction tangent
+    pub b: f64, // half-axis along the junction normal
+    pub end_parameter: f64,
+}
+
+impl EllipticalArc {
+    /// Arc ending with the wall at `end_angle` from the axis, less than a full turn after the start
+    pub fn new(start: (f64, f64), start_angle: f64, a: f64, b: f64, end_angle: f64) -> Self {
+        // The wall turns by atan2(b sin t, a cos t) at parameter t, unwrapped past π
+        let turn = (end_angle - start_angle).clamp(0.0, 2.0 * PI);
+        let mut end_parameter = (a * turn.sin()).atan2(b * turn.cos());
+        if end_parameter < 0.0 || (turn >= PI && end_parameter == 0.0) {
+            end_parameter += 2.0 * PI;
+        }
+        Self {
+            start,
+            start_angle,
+            a,
+            b,
+            end_parameter,
+        }
+    }
+
+    /// (z, r) point at the ellipse parameter `t`
+    pub fn point_at(&self, t: f64) -> (f64, f64) {
+        let (tangent, normal) = (
+            (self.start_angle.cos(), self.start_angle.sin()),
+            (-self.start_angle.sin(), self.start_angle.cos()),
+        );
+        let along = self.a * t.sin();
+        let across = self.b * (1.0 - t.cos());
+        (
+            self.start.0 + along * tangent.0 + across * normal.0,
+            self.start.1 + along * tangent.1 + across * normal.1,
+        )
+    }
+
+    /// Arc length, from a fine polyline
+    pub fn length(&self) -> f64 {
+        let points: Vec<(f64, f64)> = std::iter::once(self.start).chain(self.sample(64)).collect();
+        points
+            .windows(2)
+            .map(|pair| (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1))
+            .sum()
+    }
+
+    /// `steps` points evenly spaced in the ellipse parameter, excluding the start point
+    pub fn sample(&self, steps: usize) -> Vec<(f64, f64)> {
+        (1..=steps)
+            .map(|i| self.point_at(self.end_parameter * (i as f64) / (steps as f64)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresnel_matches_tabulated_values() {
+        // Series branch, then asymptotic branch
+        for (x, c, s) in [(1.0, 0.7798934004, 0.4382591474), (5.0, 0.5636311887, 0.4991913819)] {
+            let (fc, fs) = fresnel(x);
+            assert!((fc - c).abs() < 1e-8 && (fs - s).abs() < 1e-8, "fresnel({x}) = ({fc}, {fs})");
+        }
+        let (c, s) = fresnel(-1.0);
+        assert!((c + 0.7798934004).abs() < 1e-8 && (s + 0.4382591474).abs() < 1e-8);
+    }
+
+    #[test]
+    fn clothoid_points_follow_the_wall_angle() {
+        let clothoid = Clothoid {
+            start: (100.0, 50.0),
+            start_angle: 0.5,
+            start_curvature: 0.002,
+            end_curvature: 0.05,
+            length: 80.0,
+        };
+        // Midpoint rule on the tangent direction
+        let steps = 100_000;
+        let ds = clothoid.length / steps as f64;
+        let (mut z, mut r) = clothoid.start;
+        for k in 0..steps {
+            let angle = clothoid.angle_at((k as f64 + 0.5) * ds);
+            z += angle.cos() * ds;
+            r += angle.sin() * ds;
+        }
+        let end = clothoid.end();
+        assert!((end.0 - z).abs() < 1e-6 && (end.1 - r).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mouth_radius_is_reached_or_reported() {
+        let end = TerminationEnd::MouthRadius { radius: 120.0, end_radius: 20.0 };
+        let clothoid = Clothoid::solve((100.0, 80.0), 0.6, 0.001, &end).unwrap();
+        assert!((clothoid.end().1 - 120.0).abs() < 1e-6);
+
+        // A zero end radius leaves no finite spiral to bracket the length with
+        let end = TerminationEnd::MouthRadius { radius: 120.0, end_radius: 0.0 };
+        assert!(Clothoid::solve((100.0, 80.0), 0.6, 0.001, &end).is_err());
+    }
+
+    #[test]
+    fn mouth_plane_behind_the_junction_is_reported() {
+        let end = TerminationEnd::MouthPlane { z: 140.0, angle: PI / 2.0 };
+        let clothoid = Clothoid::solve((100.0, 80.0), 0.6, 0.001, &end).unwrap();
+        assert!((clothoid.end().0 - 140.0).abs() < 1e-6);
+        assert!((clothoid.angle_at(clothoid.length) - PI / 2.0).abs() < 1e-9);
+
+        for z in [100.0, 90.0] {
+            let end = TerminationEnd::MouthPlane { z, angle: PI / 2.0 };
+            assert!(Clothoid::solve((100.0, 80.0), 0.6, 0.001, &end).is_err());
+        }
+    }
+
+    #[test]
+    fn wall_angle_behind_the_junction_is_reported() {
+        let end = TerminationEnd::WallAngle { angle: PI / 2.0, end_radius: 20.0 };
+        let clothoid = Clothoid::solve((100.0, 80.0), 0.6, 0.001, &end).unwrap();
+        assert!((clothoid.angle_at(clothoid.length) - PI / 2.0).abs() < 1e-9);
+
+        let end = TerminationEnd::WallAngle { angle: 0.3, end_radius: 20.0 };
+        assert!(Clothoid::solve((100.0, 80.0), 0.6, 0.001, &end).is_err());
+    }
+
+    #[test]
+    fn first_crossing_is_kept_when_the_end_falls_back() {
+        // Crosses 1 at 1, falls back below and crosses again at 3
+        let end = |x: f64| if x < 2.0 { x } else { x - 2.0 };
+        assert!((first_crossing(end, 1.0, 4.0).unwrap() - 1.0).abs() < 1e-9);
+        let end = |x: f64| if x < 2.0 { 0.0 } else { x - 2.0 };
+        assert!((first_crossing(end, 1.0, 4.0).unwrap() - 3.0).abs() < 1e-9);
+        assert!(first_crossing(end, 5.0, 4.0).is_none());
+    }
+}
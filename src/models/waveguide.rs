@@ -0,0 +1,275 @@
+use crate::element_mesh::{generate_element_mesh, ElementMesh, ElementMeshOptions};
+use crate::geometry_types::{check_tilt, CartesianPoint, ProfilePoint};
+use crate::mesh::{triangulate_profiles, Mesh, Symmetry};
+use crate::models::{AzimuthalSampling, OblateSpheroidWG};
+use crate::nurbs::{fit_solid_surfaces, fit_wall_surface, BSplineSurface};
+use crate::sanitize::{check_profiles, place_profiles, Defect};
+use crate::solid::generate_solid;
+use crate::throat::{generate_source_mesh, ThroatCap};
+use std::f64::consts::PI;
+use std::io;
+
+/// Angles at which `check_symmetry` compares the profiles with their mirror images
+const SYMMETRY_CHECKS: usize = 8;
+
+/// Common interface of the waveguide models, used by mesh generation and the exporters
+pub trait Waveguide {
+    fn tilt(&self) -> f64;
+
+    /// Generate profile points along one angle
+    /// Generate profile points along one angle. Fails when the model cannot build it, e.g. a
+    /// termination that cannot be solved.
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>>;
+
+    /// Place a profile point in 3D, following the (possibly tilted) waveguide axis
+    fn place_point(&self, point: &ProfilePoint, length: f64) -> CartesianPoint {
+        CartesianPoint::from_cylindrical(point.r, point.theta, point.z).tilted(self.tilt(), length)
+    }
+
+    /// Generate the profiles of every angle over the full turn, placed by `azimuth` (a step count
+    /// for evenly spaced angles)
+    fn generate_profiles(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+    ) -> io::Result<Vec<Vec<ProfilePoint>>> {
+        self.generate_sector_profiles(length, azimuth, axial_steps, Symmetry::Full)
+    }
+
+    /// Generate the profiles covering the `symmetry` sector, edges included, or the half a
+    /// tilted waveguide keeps (see `Symmetry::for_tilt`). Fails when the model is not mirror
+    /// symmetric in the planes of the sector (see `check_symmetry`), or when the tilt folds the
+    /// wall (see `check_tilt`).
+    fn generate_sector_profiles(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+        symmetry: Symmetry,
+    ) -> io::Result<Vec<Vec<ProfilePoint>>> {
+        let symmetry = symmetry.for_tilt(self.tilt());
+        self.check_symmetry(length, axial_steps, symmetry)?;
+        let profiles = self.sample_profiles(length, azimuth.into(), axial_steps, symmetry)?;
+        check_tilt(&profiles, self.tilt(), length)?;
+        Ok(profiles)
+    }
+
+    /// Profiles at the angles `azimuth` places over the `symmetry` sector, unchecked; models
+    /// sharing work between angles override this rather than `generate_sector_profiles`
+    fn sample_profiles(
+        &self,
+        length: f64,
+        azimuth: AzimuthalSampling,
+        axial_steps: usize,
+        symmetry: Symmetry,
+    ) -> io::Result<Vec<Vec<ProfilePoint>>> {
+        azimuth.sample(symmetry, |theta| self.generate_profile(length, theta, axial_steps))
+    }
+
+    /// Fails unless the profiles at a few angles over the turn match those at their mirror
+    /// angles through the planes of `symmetry` (see `Symmetry::mirrors`)
+    fn check_symmetry(&self, length: f64, axial_steps: usize, symmetry: Symmetry) -> io::Result<()> {
+        for k in 0..SYMMETRY_CHECKS {
+            let theta = 2.0 * PI * (k as f64 + 0.3) / SYMMETRY_CHECKS as f64;
+            let profile = self.generate_profile(length, theta, axial_steps)?;
+            for mirror in symmetry.mirrors(theta) {
+                let image = self.generate_profile(length, mirror, axial_steps)?;
+                let matches = image.len() == profile.len()
+                    && profile.iter().zip(&image).all(|(a, b)| {
+                        let tolerance = 1e-9 * a.z.abs().max(a.r.abs()).max(1.0);
+                        (a.z - b.z).abs() <= tolerance && (a.r - b.r).abs() <= tolerance
+                    });
+                if !matches {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{:?} symmetry: the profile at θ = {:.4} does not mirror the one at θ = {:.4}",
+                            symmetry, theta, mirror
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Profiles of the `symmetry` sector (see `generate_sector_profiles`) with the degenerate
+    /// triangles of their placed grid. With `repair`, bad points are re-sampled at a slightly
+    /// shifted angle (see `repair_profiles`) and the defects returned are those left afterwards.
+    fn generate_checked_profiles(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+        symmetry: Symmetry,
+        repair: bool,
+    ) -> io::Result<(Vec<Vec<ProfilePoint>>, Vec<Defect>)> {
+        let symmetry = symmetry.for_tilt(self.tilt());
+        Ok(check_profiles(
+            self.generate_sector_profiles(length, azimuth, axial_steps, symmetry)?,
+            symmetry,
+            repair,
+            |point| self.place_point(point, length),
+            |theta| self.generate_profile(length, theta, axial_steps).ok(),
+        ))
+    }
+
+    /// Placed profiles of the `symmetry` sector (see `generate_sector_profiles`) as a grid,
+    /// `grid[θ index][z index]`, with its degenerate triangles, checked and repaired as by
+    /// `generate_checked_profiles`
+    fn generate_grid(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+        symmetry: Symmetry,
+        repair: bool,
+    ) -> io::Result<(Vec<Vec<CartesianPoint>>, Vec<Defect>)> {
+        let (profiles, defects) = self.generate_checked_profiles(length, azimuth, axial_steps, symmetry, repair)?;
+        let grid = place_profiles(&profiles, symmetry.for_tilt(self.tilt()), |point| self.place_point(point, length));
+        Ok((grid, defects))
+    }
+
+    /// Degenerate triangles of the full mesh, before any repair
+    fn check_mesh(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+    ) -> io::Result<Vec<Defect>> {
+        Ok(self.generate_grid(length, azimuth, axial_steps, Symmetry::Full, false)?.1)
+    }
+
+    /// Generate full 3D mesh, repaired as by `generate_grid` (which also returns the defects left)
+    fn generate_mesh(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+    ) -> io::Result<Vec<[CartesianPoint; 3]>> {
+        Ok(triangulate_profiles(&self.generate_grid(length, azimuth, axial_steps, Symmetry::Full, true)?.0))
+    }
+
+    /// Generate the mesh of the `symmetry` sector only, the angles on its edges lying exactly on
+    /// the mirror planes, or of the half a tilted waveguide keeps (see `Symmetry::for_tilt`);
+    /// the mesh records which. `azimuth` counts steps over the full turn.
+    fn generate_sector_mesh(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+        symmetry: Symmetry,
+    ) -> io::Result<Mesh> {
+        let symmetry = symmetry.for_tilt(self.tilt());
+        Ok(Mesh::from_sector_grid(&self.generate_grid(length, azimuth, axial_steps, symmetry, true)?.0, symmetry))
+    }
+
+    /// Generate a BEM surface mesh with elements sized by `options`, angles spread evenly along
+    /// the mouth contour, and the defects left by repairing its grid
+    fn generate_element_mesh(&self, length: f64, options: &ElementMeshOptions) -> io::Result<(ElementMesh, Vec<Defect>)> {
+        let max_edge = options.size.max_edge();
+        let axial_steps = ((8.0 * length / max_edge).ceil() as usize).max(64);
+        let options = ElementMeshOptions {
+            symmetry: options.symmetry.for_tilt(self.tilt()),
+            ..*options
+        };
+        generate_element_mesh(
+            &options,
+            |azimuth, symmetry| Ok(self.generate_checked_profiles(length, azimuth, axial_steps, symmetry, true)?.0),
+            |point| self.place_point(point, length),
+        )
+    }
+
+    /// Source surface matching the wavefront entering the throat
+    fn throat_cap(&self) -> ThroatCap {
+        ThroatCap::Flat
+    }
+
+    /// Shell thickness the mouth lip of the model closes onto (see `Termination::Lip`)
+    fn lip_thickness(&self) -> Option<f64> {
+        None
+    }
+
+    /// Fails when the model ends in a lip closing onto a shell of another thickness, whose
+    /// outer wall would cut through the lip
+    fn check_lip(&self, thickness: f64) -> io::Result<()> {
+        match self.lip_thickness() {
+            Some(lip) if (lip - thickness).abs() > 1e-9 * lip.abs().max(1.0) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the lip closes onto a wall {} thick, not {}", lip, thickness),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Generate the wall mesh with the throat closed by `cap`, tagged as the source
+    fn generate_source_mesh(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+        cap: &ThroatCap,
+    ) -> io::Result<Mesh> {
+        generate_source_mesh(&self.generate_mesh(length, azimuth, axial_steps)?, cap)
+    }
+
+    /// Generate a closed solid with walls of `thickness` around the profiles repaired as by
+    /// `generate_checked_profiles`. Fails when `thickness` differs from that of the lip of the
+    /// model (see `check_lip`).
+    fn generate_solid(
+        &self,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial_steps: usize,
+        thickness: f64,
+    ) -> io::Result<Vec<[CartesianPoint; 3]>> {
+        self.check_lip(thickness)?;
+        let (profiles, _) = self.generate_checked_profiles(length, azimuth, axial_steps, Symmetry::Full, true)?;
+        Ok(generate_solid(&profiles, thickness, |point| self.place_point(point, length)))
+    }
+
+    /// Fit the wall with a B-spline surface within `tolerance`, periodic around the axis, and
+    /// the deviation reached (see `fit_surfaces`)
+    fn fit_wall_surface(&self, length: f64, tolerance: f64) -> io::Result<(BSplineSurface, f64)> {
+        fit_wall_surface(tolerance, |az, ax| {
+            Ok(self.generate_grid(length, az, ax, Symmetry::Full, true)?.0)
+        })
+    }
+
+    /// Fit the faces of the solid with walls of `thickness` (see `shell_faces`), around the
+    /// repaired profiles, with B-spline surfaces within `tolerance`, and the deviation reached.
+    /// Fails like `generate_solid` on a lip of another thickness.
+    fn fit_solid_surfaces(
+        &self,
+        length: f64,
+        thickness: f64,
+        tolerance: f64,
+    ) -> io::Result<(Vec<BSplineSurface>, f64)> {
+        self.check_lip(thickness)?;
+        fit_solid_surfaces(
+            tolerance,
+            thickness,
+            |az, ax| Ok(self.generate_checked_profiles(length, az, ax, Symmetry::Full, true)?.0),
+            |point| self.place_point(point, length),
+        )
+    }
+}
+
+impl<T: OblateSpheroidWG> Waveguide for T {
+    fn tilt(&self) -> f64 {
+        OblateSpheroidWG::tilt(self)
+    }
+
+    fn generate_profile(&self, length: f64, theta: f64, resolution: usize) -> io::Result<Vec<ProfilePoint>> {
+        OblateSpheroidWG::generate_profile(self, length, theta, resolution)
+    }
+
+    fn throat_cap(&self) -> ThroatCap {
+        ThroatCap::wavefront(self.r_init(), self.alpha_init())
+    }
+
+    fn lip_thickness(&self) -> Option<f64> {
+        OblateSpheroidWG::termination(self).and_then(|termination| termination.lip_thickness())
+    }
+}
@@ -0,0 +1,508 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::solid::shell_faces;
+use std::io;
+
+const ORIGIN: CartesianPoint = CartesianPoint { x: 0.0, y: 0.0, z: 0.0 };
+
+/// Non-rational B-spline curve over an explicit knot vector
+#[derive(Debug, Clone)]
+pub struct BSplineCurve {
+    pub degree: usize,
+    pub control_points: Vec<CartesianPoint>,
+    pub knots: Vec<f64>,
+    pub closed: bool, // periodic, the first `degree` control points repeated at the end
+}
+
+impl BSplineCurve {
+    /// Clamped interpolation of `points` with chord-length parameters, cubic at most. Repeated
+    /// consecutive points are interpolated once; fails with fewer than two distinct points.
+    pub fn interpolate(points: &[CartesianPoint]) -> io::Result<Self> {
+        let mut distinct: Vec<CartesianPoint> = Vec::with_capacity(points.len());
+        for &point in points {
+            if distinct.last().is_none_or(|&last| (point - last).norm() > 0.0) {
+                distinct.push(point);
+            }
+        }
+        if distinct.len() < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot interpolate {} distinct points", distinct.len()),
+            ));
+        }
+
+        let degree = 3.min(distinct.len() - 1);
+        let parameters = chord_parameters(std::slice::from_ref(&distinct), distinct.len());
+        let knots = averaged_knots(&parameters, degree);
+        Ok(BSplineCurve {
+            degree,
+            control_points: BandedSystem::new(&knots, degree, &parameters).solve(&distinct),
+            knots,
+            closed: false,
+        })
+    }
+
+    pub fn domain(&self) -> (f64, f64) {
+        (self.knots[self.degree], self.knots[self.control_points.len()])
+    }
+
+    pub fn evaluate(&self, t: f64) -> CartesianPoint {
+        let span = find_span(&self.knots, self.degree, self.control_points.len(), t);
+        basis(&self.knots, self.degree, span, t)
+            .iter()
+            .enumerate()
+            .fold(ORIGIN, |sum, (k, &weight)| sum + self.control_points[span - self.degree + k] * weight)
+    }
+}
+
+/// Tensor-product non-rational B-spline surface, periodic in u (around the axis) and clamped
+/// in v (along the profiles)
+#[derive(Debug, Clone)]
+pub struct BSplineSurface {
+    pub degree_u: usize,
+    pub degree_v: usize,
+    pub control_points: Vec<Vec<CartesianPoint>>, // [u index][v index], u wrap-around included
+    pub knots_u: Vec<f64>,
+    pub knots_v: Vec<f64>,
+    pub parameters_v: Vec<f64>, // v of the interpolated points, u being their θ index
+}
+
+impl BSplineSurface {
+    /// Bicubic interpolation of `grid[θ index][point index]`: periodic uniform in θ, clamped
+    /// with averaged chord-length parameters along the profiles (cubic at most, linear for
+    /// two-point profiles). The boundary rows are interpolated exactly. Fails on a grid without
+    /// profiles or with profiles of fewer than two points.
+    pub fn interpolate(grid: &[Vec<CartesianPoint>]) -> io::Result<Self> {
+        let count = grid.iter().map(Vec::len).min().unwrap_or(0);
+        if count < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot interpolate {} profiles of {} points", grid.len(), count),
+            ));
+        }
+        let degree_v = 3.min(count - 1);
+
+        let parameters_v = chord_parameters(grid, count);
+        let knots_v = averaged_knots(&parameters_v, degree_v);
+
+        let collocation = BandedSystem::new(&knots_v, degree_v, &parameters_v);
+        let along_v: Vec<Vec<CartesianPoint>> = grid.iter().map(|profile| collocation.solve(&profile[..count])).collect();
+
+        // Around the axis, sample i sits at u = i on uniform unit knots
+        let n = grid.len();
+        let columns: Vec<Vec<CartesianPoint>> = (0..count)
+            .map(|j| periodic_control_points(&along_v.iter().map(|row| row[j]).collect::<Vec<_>>()))
+            .collect();
+        let control_points = (0..n + 3)
+            .map(|k| (0..count).map(|j| columns[j][(k + n - 1) % n]).collect())
+            .collect();
+        let knots_u = (0..n + 7).map(|i| i as f64 - 3.0).collect();
+
+        Ok(BSplineSurface {
+            degree_u: 3,
+            degree_v,
+            control_points,
+            knots_u,
+            knots_v,
+            parameters_v,
+        })
+    }
+
+    /// Number of distinct control points around the axis
+    pub fn periods(&self) -> usize {
+        self.control_points.len() - self.degree_u
+    }
+
+    pub fn evaluate(&self, u: f64, v: f64) -> CartesianPoint {
+        let u = u.rem_euclid(self.periods() as f64);
+        let v = v.clamp(0.0, 1.0);
+        let span_u = find_span(&self.knots_u, self.degree_u, self.control_points.len(), u);
+        let span_v = find_span(&self.knots_v, self.degree_v, self.control_points[0].len(), v);
+        let basis_u = basis(&self.knots_u, self.degree_u, span_u, u);
+        let basis_v = basis(&self.knots_v, self.degree_v, span_v, v);
+
+        let mut point = ORIGIN;
+        for (a, &weight_u) in basis_u.iter().enumerate() {
+            let row = &self.control_points[span_u - self.degree_u + a];
+            for (b, &weight_v) in basis_v.iter().enumerate() {
+                point = point + row[span_v - self.degree_v + b] * (weight_u * weight_v);
+            }
+        }
+        point
+    }
+
+    /// Closed boundary curve at the start (v = 0) or end (v = 1) of the profiles
+    pub fn boundary(&self, end: bool) -> BSplineCurve {
+        let j = if end { self.control_points[0].len() - 1 } else { 0 };
+        BSplineCurve {
+            degree: self.degree_u,
+            control_points: self.control_points.iter().map(|row| row[j]).collect(),
+            knots: self.knots_u.clone(),
+            closed: true,
+        }
+    }
+
+    /// Iso-curve u = 0 along the profiles, where the periodic surface closes
+    pub fn seam(&self) -> BSplineCurve {
+        // Uniform cubic basis at a knot: 1/6, 2/3, 1/6 on the three control points around it
+        let rows = &self.control_points;
+        BSplineCurve {
+            degree: self.degree_v,
+            control_points: (0..rows[0].len())
+                .map(|j| (rows[0][j] + rows[1][j] * 4.0 + rows[2][j]) * (1.0 / 6.0))
+                .collect(),
+            knots: self.knots_v.clone(),
+            closed: false,
+        }
+    }
+
+    /// Distance from `point` to the surface, searched by Gauss-Newton from `(u, v)`
+    pub fn distance_from(&self, point: &CartesianPoint, (mut u, mut v): (f64, f64)) -> f64 {
+        let h = 1e-6;
+        for _ in 0..12 {
+            let s = self.evaluate(u, v);
+            let su = (self.evaluate(u + h, v) - self.evaluate(u - h, v)) * (0.5 / h);
+            let (v0, v1) = ((v - h).max(0.0), (v + h).min(1.0));
+            let sv = (self.evaluate(u, v1) - self.evaluate(u, v0)) * (1.0 / (v1 - v0));
+            let residual = s - *point;
+
+            let (a, b, c) = (su.dot(&su), su.dot(&sv), sv.dot(&sv));
+            let (gu, gv) = (su.dot(&residual), sv.dot(&residual));
+            let determinant = a * c - b * b;
+            if determinant.abs() < 1e-30 {
+                break;
+            }
+            u -= (c * gu - b * gv) / determinant;
+            v = (v - (a * gv - b * gu) / determinant).clamp(0.0, 1.0);
+        }
+        (self.evaluate(u, v) - *point).norm()
+    }
+}
+
+/// Fits one surface per grid returned by `sample(azimuth_steps, axial_steps)`, doubling each
+/// count until every grid sampled twice as finely in that direction lies within `tolerance`
+/// of its surface, or up to 512 steps. Returns the surfaces with the deviation they reached,
+/// above `tolerance` when the step limit stopped the refinement.
+pub fn fit_surfaces(
+    tolerance: f64,
+    sample: impl Fn(usize, usize) -> io::Result<Vec<Vec<Vec<CartesianPoint>>>>,
+) -> io::Result<(Vec<BSplineSurface>, f64)> {
+    let (mut azimuth_steps, mut axial_steps) = (16, 16);
+    let mut grids = sample(azimuth_steps, axial_steps)?;
+    loop {
+        let surfaces = grids
+            .iter()
+            .map(|grid| BSplineSurface::interpolate(grid))
+            .collect::<io::Result<Vec<BSplineSurface>>>()?;
+        let deviation = |finer: Vec<Vec<Vec<CartesianPoint>>>| {
+            surfaces
+                .iter()
+                .zip(&finer)
+                .map(|(surface, grid)| max_deviation(surface, grid))
+                .fold(0.0, f64::max)
+        };
+        let azimuth_deviation = deviation(sample(2 * azimuth_steps, axial_steps)?);
+        let axial_deviation = deviation(sample(azimuth_steps, 2 * axial_steps)?);
+
+        let (refine_azimuth, refine_axial) = (azimuth_deviation > tolerance, axial_deviation > tolerance);
+        if (!refine_azimuth && !refine_axial) || azimuth_steps.max(axial_steps) >= 512 {
+            return Ok((surfaces, azimuth_deviation.max(axial_deviation)));
+        }
+
+        if refine_azimuth {
+            azimuth_steps *= 2;
+        }
+        if refine_axial {
+            axial_steps *= 2;
+        }
+        grids = sample(azimuth_steps, axial_steps)?;
+    }
+}
+
+/// Fits a wall, given as the placed grid `grid(azimuth_steps, axial_steps)` over the full turn,
+/// with one periodic surface (see `fit_surfaces`)
+pub fn fit_wall_surface(
+    tolerance: f64,
+    grid: impl Fn(usize, usize) -> io::Result<Vec<Vec<CartesianPoint>>>,
+) -> io::Result<(BSplineSurface, f64)> {
+    let (mut surfaces, deviation) = fit_surfaces(tolerance, |azimuth_steps, axial_steps| {
+        Ok(vec![grid(azimuth_steps, axial_steps)?])
+    })?;
+    Ok((surfaces.remove(0), deviation))
+}
+
+/// Fits the faces of the solid with walls of `thickness` (see `shell_faces`) around the
+/// profiles `profiles(azimuth_steps, axial_steps)` of the full turn, placed in 3D by `place`
+pub fn fit_solid_surfaces(
+    tolerance: f64,
+    thickness: f64,
+    profiles: impl Fn(usize, usize) -> io::Result<Vec<Vec<ProfilePoint>>>,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+) -> io::Result<(Vec<BSplineSurface>, f64)> {
+    fit_surfaces(tolerance, |azimuth_steps, axial_steps| {
+        Ok(shell_faces(&profiles(azimuth_steps, axial_steps)?, thickness)
+            .iter()
+            .map(|face| face.iter().map(|profile| profile.iter().map(&place).collect()).collect())
+            .collect())
+    })
+}
+
+/// Largest distance from the points of a finer grid of the same family to the surface
+fn max_deviation(surface: &BSplineSurface, grid: &[Vec<CartesianPoint>]) -> f64 {
+    let scale = surface.periods() as f64 / grid.len() as f64;
+    let parameters = &surface.parameters_v;
+    grid.iter()
+        .enumerate()
+        .flat_map(|(i, profile)| {
+            profile.iter().enumerate().map(move |(j, point)| {
+                // Start from the coarse parameters at the same fraction of the profile
+                let position = j as f64 / (profile.len() - 1).max(1) as f64 * (parameters.len() - 1) as f64;
+                let k = (position.floor() as usize).min(parameters.len() - 2);
+                let v = parameters[k] + (position - k as f64) * (parameters[k + 1] - parameters[k]);
+                surface.distance_from(point, (i as f64 * scale, v))
+            })
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Chord-length parameters of the first `count` points, averaged over the profiles
+fn chord_parameters(profiles: &[Vec<CartesianPoint>], count: usize) -> Vec<f64> {
+    let mut parameters = vec![0.0; count];
+    let mut used = 0;
+    for profile in profiles {
+        let lengths: Vec<f64> = profile[..count].windows(2).map(|pair| (pair[1] - pair[0]).norm()).collect();
+        let total: f64 = lengths.iter().sum();
+        if total > 0.0 && total.is_finite() {
+            let mut cumulative = 0.0;
+            for (j, length) in lengths.iter().enumerate() {
+                cumulative += length;
+                parameters[j + 1] += cumulative / total;
+            }
+            used += 1;
+        }
+    }
+    for (j, parameter) in parameters.iter_mut().enumerate() {
+        *parameter = if used > 0 {
+            *parameter / used as f64
+        } else {
+            j as f64 / (count - 1) as f64
+        };
+    }
+    parameters[count - 1] = 1.0;
+    parameters
+}
+
+/// Clamped knots by averaging the parameters, which keeps the collocation matrix well conditioned
+fn averaged_knots(parameters: &[f64], degree: usize) -> Vec<f64> {
+    let count = parameters.len();
+    let mut knots = vec![0.0; degree + 1];
+    for j in 1..count - degree {
+        knots.push(parameters[j..j + degree].iter().sum::<f64>() / degree as f64);
+    }
+    knots.extend(std::iter::repeat_n(1.0, degree + 1));
+    knots
+}
+
+/// Knot span index holding `t` (The NURBS Book, A2.1)
+fn find_span(knots: &[f64], degree: usize, control_count: usize, t: f64) -> usize {
+    if t >= knots[control_count] {
+        return control_count - 1;
+    }
+    if t <= knots[degree] {
+        return degree;
+    }
+    let (mut low, mut high) = (degree, control_count);
+    let mut middle = (low + high) / 2;
+    while t < knots[middle] || t >= knots[middle + 1] {
+        if t < knots[middle] {
+            high = middle;
+        } else {
+            low = middle;
+        }
+        middle = (low + high) / 2;
+    }
+    middle
+}
+
+/// Non-zero basis functions at `t` in knot span `span` (The NURBS Book, A2.2)
+fn basis(knots: &[f64], degree: usize, span: usize, t: f64) -> Vec<f64> {
+    let mut values = vec![0.0; degree + 1];
+    let mut left = vec![0.0; degree + 1];
+    let mut right = vec![0.0; degree + 1];
+    values[0] = 1.0;
+    for j in 1..=degree {
+        left[j] = t - knots[span + 1 - j];
+        right[j] = knots[span + j] - t;
+        let mut saved = 0.0;
+        for r in 0..j {
+            let temp = values[r] / (right[r + 1] + left[j - r]);
+            values[r] = saved + right[r + 1] * temp;
+            saved = left[j - r] * temp;
+        }
+        values[j] = saved;
+    }
+    values
+}
+
+/// Collocation matrix of a clamped B-spline at the interpolation parameters, factorized once.
+/// It is banded and totally positive, so elimination needs no pivoting.
+struct BandedSystem {
+    matrix: Vec<Vec<f64>>, // LU factors in place
+    degree: usize,
+}
+
+impl BandedSystem {
+    fn new(knots: &[f64], degree: usize, parameters: &[f64]) -> Self {
+        let n = parameters.len();
+        let mut matrix = vec![vec![0.0; n]; n];
+        for (row, &t) in matrix.iter_mut().zip(parameters) {
+            let span = find_span(knots, degree, n, t);
+            for (k, value) in basis(knots, degree, span, t).into_iter().enumerate() {
+                row[span - degree + k] = value;
+            }
+        }
+
+        for k in 0..n {
+            let band = (k + degree + 1).min(n);
+            let (upper, lower) = matrix.split_at_mut(k + 1);
+            let pivot = &upper[k];
+            for row in lower.iter_mut().take(degree) {
+                let factor = row[k] / pivot[k];
+                row[k] = factor;
+                for (value, above) in row[k + 1..band].iter_mut().zip(&pivot[k + 1..band]) {
+                    *value -= factor * above;
+                }
+            }
+        }
+        BandedSystem { matrix, degree }
+    }
+
+    fn solve(&self, rhs: &[CartesianPoint]) -> Vec<CartesianPoint> {
+        let n = rhs.len();
+        let band = |k: usize| (k + self.degree + 1).min(n);
+        let mut x = rhs.to_vec();
+        for k in 0..n {
+            for i in k + 1..band(k) {
+                x[i] = x[i] - x[k] * self.matrix[i][k];
+            }
+        }
+        for k in (0..n).rev() {
+            let value = x[k + 1..band(k)]
+                .iter()
+                .zip(&self.matrix[k][k + 1..band(k)])
+                .fold(x[k], |value, (&xj, &factor)| value - xj * factor);
+            x[k] = value * (1.0 / self.matrix[k][k]);
+        }
+        x
+    }
+}
+
+/// Control points of the periodic uniform cubic B-spline through `points` at its knots:
+/// solves the cyclic system (c[i-1] + 4 c[i] + c[i+1]) / 6 = points[i]
+fn periodic_control_points(points: &[CartesianPoint]) -> Vec<CartesianPoint> {
+    let n = points.len();
+    if n < 3 {
+        return points.to_vec();
+    }
+    let (side, diagonal) = (1.0 / 6.0, 4.0 / 6.0);
+
+    // Sherman-Morrison on top of the Thomas algorithm for the corner terms
+    let gamma = -diagonal;
+    let mut main = vec![diagonal; n];
+    main[0] = diagonal - gamma;
+    main[n - 1] = diagonal - side * side / gamma;
+
+    let thomas = |rhs: &[CartesianPoint]| -> Vec<CartesianPoint> {
+        let mut c = vec![0.0; n];
+        let mut d = rhs.to_vec();
+        c[0] = side / main[0];
+        d[0] = d[0] * (1.0 / main[0]);
+        for i in 1..n {
+            let m = main[i] - side * c[i - 1];
+            c[i] = side / m;
+            d[i] = (d[i] - d[i - 1] * side) * (1.0 / m);
+        }
+        for i in (0..n - 1).rev() {
+            d[i] = d[i] - d[i + 1] * c[i];
+        }
+        d
+    };
+
+    let y = thomas(points);
+    let mut u = vec![ORIGIN; n];
+    u[0] = CartesianPoint { x: gamma, y: gamma, z: gamma };
+    u[n - 1] = CartesianPoint { x: side, y: side, z: side };
+    let z = thomas(&u);
+
+    // Same scalar correction for each coordinate
+    let factor = |pick: fn(&CartesianPoint) -> f64| {
+        (pick(&y[0]) + side * pick(&y[n - 1]) / gamma) / (1.0 + pick(&z[0]) + side * pick(&z[n - 1]) / gamma)
+    };
+    let (fx, fy, fz) = (factor(|p| p.x), factor(|p| p.y), factor(|p| p.z));
+    y.iter()
+        .zip(&z)
+        .map(|(y, z)| CartesianPoint {
+            x: y.x - fx * z.x,
+            y: y.y - fy * z.y,
+            z: y.z - fz * z.z,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// Flared wall sampled on `azimuth_steps` profiles of `axial_steps` points
+    fn flare(azimuth_steps: usize, axial_steps: usize) -> Vec<Vec<CartesianPoint>> {
+        (0..azimuth_steps)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / azimuth_steps as f64;
+                (0..axial_steps)
+                    .map(|j| {
+                        let z = 100.0 * j as f64 / (axial_steps - 1) as f64;
+                        CartesianPoint::from_cylindrical(10.0 + 0.004 * z * z, theta, z)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn curve_passes_through_its_points() {
+        let points: Vec<CartesianPoint> = (0..7)
+            .map(|k| CartesianPoint { x: k as f64, y: (k as f64).sin() * 3.0, z: 0.5 * (k * k) as f64 })
+            .collect();
+        let curve = BSplineCurve::interpolate(&points).unwrap();
+        for (point, t) in points.iter().zip(chord_parameters(std::slice::from_ref(&points), points.len())) {
+            assert!((curve.evaluate(t) - *point).norm() < 1e-9);
+        }
+
+        // Repeated points are interpolated once
+        let repeated: Vec<CartesianPoint> = points.iter().flat_map(|&point| [point, point]).collect();
+        let curve = BSplineCurve::interpolate(&repeated).unwrap();
+        assert_eq!(curve.control_points.len(), points.len());
+        assert!(BSplineCurve::interpolate(&[]).is_err());
+        assert!(BSplineCurve::interpolate(&[ORIGIN, ORIGIN]).is_err());
+    }
+
+    #[test]
+    fn surface_passes_through_its_grid() {
+        let grid = flare(12, 9);
+        let surface = BSplineSurface::interpolate(&grid).unwrap();
+        for (i, profile) in grid.iter().enumerate() {
+            for (point, &v) in profile.iter().zip(&surface.parameters_v) {
+                assert!((surface.evaluate(i as f64, v) - *point).norm() < 1e-9);
+            }
+        }
+        assert!(BSplineSurface::interpolate(&[]).is_err());
+        assert!(BSplineSurface::interpolate(&[vec![ORIGIN]]).is_err());
+    }
+
+    #[test]
+    fn fit_reaches_the_tolerance() {
+        let (surface, deviation) = fit_wall_surface(0.01, |azimuth, axial| Ok(flare(azimuth, axial))).unwrap();
+        assert!(deviation <= 0.01);
+        assert!(max_deviation(&surface, &flare(100, 100)) <= 0.02);
+    }
+}
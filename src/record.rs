@@ -0,0 +1,375 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::models::{
+    AxisymOSCWG, AxisymOSWG, AzimuthalSampling, ClassicHornWG, EllipsoidalOSWG, LeCleachWG, RectOSCWG, RectangularMorphOSWG, RectangularOSWG, TabulatedWG, Waveguide,
+};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const GENERATOR: &str = "compression-waveguide";
+
+/// Start of every record embedded by the exporters, as written by `DesignRecord::to_json`
+const MARKER: &str = "{\"generator\":\"compression-waveguide\"";
+
+/// Model struct a design was generated from, with all its parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "struct")]
+pub enum DesignModel {
+    EllipsoidalOSWG(EllipsoidalOSWG),
+    AxisymOSWG(AxisymOSWG),
+    RectangularOSWG(RectangularOSWG),
+    RectangularMorphOSWG(RectangularMorphOSWG),
+    AxisymOSCWG(AxisymOSCWG),
+    RectOSCWG(RectOSCWG),
+    ClassicHornWG(ClassicHornWG),
+    LeCleachWG(LeCleachWG),
+    TabulatedWG(TabulatedWG),
+}
+
+/// Evaluates `$body` with `$model` bound to the model struct of a `DesignModel`, as a
+/// `Waveguide`: the one dispatch over the models
+macro_rules! with_waveguide {
+    ($design:expr, $model:ident => $body:expr) => {
+        match $design {
+            DesignModel::EllipsoidalOSWG($model) => $body,
+            DesignModel::AxisymOSWG($model) => $body,
+            DesignModel::RectangularOSWG($model) => $body,
+            DesignModel::RectangularMorphOSWG($model) => $body,
+            DesignModel::AxisymOSCWG($model) => $body,
+            DesignModel::RectOSCWG($model) => $body,
+            DesignModel::ClassicHornWG($model) => $body,
+            DesignModel::LeCleachWG($model) => $body,
+            DesignModel::TabulatedWG($model) => $body,
+        }
+    };
+}
+
+impl DesignModel {
+    /// Name of the model struct
+    pub fn name(&self) -> &'static str {
+        match self {
+            DesignModel::EllipsoidalOSWG(_) => "EllipsoidalOSWG",
+            DesignModel::AxisymOSWG(_) => "AxisymOSWG",
+            DesignModel::RectangularOSWG(_) => "RectangularOSWG",
+            DesignModel::RectangularMorphOSWG(_) => "RectangularMorphOSWG",
+            DesignModel::AxisymOSCWG(_) => "AxisymOSCWG",
+            DesignModel::RectOSCWG(_) => "RectOSCWG",
+            DesignModel::ClassicHornWG(_) => "ClassicHornWG",
+            DesignModel::LeCleachWG(_) => "LeCleachWG",
+            DesignModel::TabulatedWG(_) => "TabulatedWG",
+        }
+    }
+}
+
+/// Resolution along the profiles: a point count, or a step length giving one over the length
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum AxialResolution {
+    Steps(usize),
+    StepLength(f64),
+}
+
+impl AxialResolution {
+    /// Points per profile over `length`
+    pub fn steps(&self, length: f64) -> usize {
+        match *self {
+            AxialResolution::Steps(steps) => steps,
+            AxialResolution::StepLength(step_length) => (length / step_length).ceil() as usize + 1,
+        }
+    }
+
+    /// Distance between profile points over `length`
+    pub fn step_length(&self, length: f64) -> f64 {
+        match *self {
+            AxialResolution::Steps(steps) => length / (steps.max(2) - 1) as f64,
+            AxialResolution::StepLength(step_length) => step_length,
+        }
+    }
+}
+
+impl From<usize> for AxialResolution {
+    fn from(steps: usize) -> Self {
+        AxialResolution::Steps(steps)
+    }
+}
+
+impl From<f64> for AxialResolution {
+    fn from(step_length: f64) -> Self {
+        AxialResolution::StepLength(step_length)
+    }
+}
+
+/// What an export holds, built from the waveguide of its record
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum DesignOutput {
+    /// The wall, as by `generate_mesh`
+    #[default]
+    Wall,
+    /// The closed solid of `generate_solid`, with walls of `thickness`
+    Solid { thickness: f64 },
+    /// Built from the wall by further steps (a baffle, an enclosure, a BEM mesh...), named here;
+    /// the record only gives the waveguide it started from
+    Derived(String),
+}
+
+/// Everything needed to regenerate an exported design: the model and its parameters, the
+/// generation arguments, what was exported and the crate version that wrote it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesignRecord {
+    pub generator: String,
+    pub version: String,
+    pub model: DesignModel,
+    pub length: f64,
+    pub azimuth: AzimuthalSampling,
+    pub axial: AxialResolution,
+    #[serde(default)] // records from before the output was recorded are of the wall
+    pub output: DesignOutput,
+}
+
+impl DesignRecord {
+    pub fn new(
+        model: DesignModel,
+        length: f64,
+        azimuth: impl Into<AzimuthalSampling>,
+        axial: impl Into<AxialResolution>,
+    ) -> Self {
+        Self {
+            generator: GENERATOR.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            model,
+            length,
+            azimuth: azimuth.into(),
+            axial: axial.into(),
+            output: DesignOutput::Wall,
+        }
+    }
+
+    /// Single line JSON, as embedded in the exports
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(text: &str) -> io::Result<Self> {
+        serde_json::from_str(text).map_err(io::Error::from)
+    }
+
+    /// Generator, version and model struct, for headers too short for the whole record
+    pub fn summary(&self) -> String {
+        format!("{} {} {}", self.generator, self.version, self.model.name())
+    }
+
+    /// Profiles of every angle, as generated for the export
+    pub fn generate_profiles(&self) -> io::Result<Vec<Vec<ProfilePoint>>> {
+        let steps = self.axial.steps(self.length);
+        with_waveguide!(&self.model, model => model.generate_profiles(self.length, self.azimuth, steps))
+    }
+
+    /// Place a profile point in 3D, following the model's axis
+    pub fn place_point(&self, point: &ProfilePoint) -> CartesianPoint {
+        with_waveguide!(&self.model, model => model.place_point(point, self.length))
+    }
+
+    /// Triangles of the export: the wall or the solid, as generated for it. Fails for derived
+    /// exports, which the record alone does not rebuild.
+    pub fn generate_mesh(&self) -> io::Result<Vec<[CartesianPoint; 3]>> {
+        let steps = self.axial.steps(self.length);
+        match &self.output {
+            DesignOutput::Wall => self.generate_wall(),
+            DesignOutput::Solid { thickness } => with_waveguide!(&self.model, model => {
+                model.generate_solid(self.length, self.azimuth, steps, *thickness)
+            }),
+            DesignOutput::Derived(name) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} derived from the recorded {}, not regenerated", name, self.model.name()),
+            )),
+        }
+    }
+
+    /// Full 3D mesh of the wall, as generated for the export
+    pub fn generate_wall(&self) -> io::Result<Vec<[CartesianPoint; 3]>> {
+        let steps = self.axial.steps(self.length);
+        with_waveguide!(&self.model, model => model.generate_mesh(self.length, self.azimuth, steps))
+    }
+}
+
+/// JSON sidecar of an export, `<file>.json` next to it
+pub fn sidecar_path(path: impl AsRef<Path>) -> PathBuf {
+    let mut name = OsString::from(path.as_ref().as_os_str());
+    name.push(".json");
+    PathBuf::from(name)
+}
+
+/// Writes the record as indented JSON, e.g. to the `sidecar_path` of an export
+pub fn write_record(record: &DesignRecord, path: impl AsRef<Path>) -> io::Result<()> {
+    let text = serde_json::to_string_pretty(record).map_err(io::Error::from)?;
+    fs::write(path, text + "\n")
+}
+
+/// First record embedded in `text`
+fn find_record(text: &str) -> Option<io::Result<DesignRecord>> {
+    let start = text.find(MARKER)?;
+    let record = serde_json::Deserializer::from_str(&text[start..]).into_iter::<DesignRecord>().next()?;
+    Some(record.map_err(io::Error::from))
+}
+
+/// Data columns of the IGES start section, where long records span several lines
+fn iges_start_section(text: &str) -> String {
+    text.lines()
+        .filter(|line| line.len() == 80 && line.as_bytes()[72] == b'S')
+        .filter_map(|line| line.get(..72))
+        .collect()
+}
+
+/// Text of STEP strings as written by `write_step`: doubled apostrophes and backslashes, and
+/// \X2\ UCS-2 hex runs
+fn step_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(['\'', '\\']) {
+        unescaped.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if let Some(after) = rest.strip_prefix("''") {
+            unescaped.push('\'');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("\\\\") {
+            unescaped.push('\\');
+            rest = after;
+        } else if let Some((hex, after)) = rest.strip_prefix("\\X2\\").and_then(|after| after.split_once("\\X0\\")) {
+            let units: Vec<u16> = (0..hex.len() / 4)
+                .filter_map(|k| hex.get(4 * k..4 * k + 4).and_then(|unit| u16::from_str_radix(unit, 16).ok()))
+                .collect();
+            unescaped.extend(char::decode_utf16(units).map(|character| character.unwrap_or(char::REPLACEMENT_CHARACTER)));
+            rest = after;
+        } else {
+            unescaped.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Reads the design record of a file written by the exporters: a JSON record, the record
+/// embedded in the file (3MF packages as stored by `write_3mf`), or else its JSON sidecar.
+/// The record may come from another crate `version` than the one regenerating it.
+pub fn read_record(path: impl AsRef<Path>) -> io::Result<DesignRecord> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    if extension.eq_ignore_ascii_case("step") || extension.eq_ignore_ascii_case("stp") {
+        text = step_unescape(&text);
+    }
+    let record = if extension.eq_ignore_ascii_case("json") {
+        DesignRecord::from_json(&text)?
+    } else {
+        // The IGES start section first, its lines would cut the record short in the plain text
+        let embedded = find_record(&iges_start_section(&text))
+            .or_else(|| find_record(&text))
+            .or_else(|| find_record(&xml_unescape(&text)));
+        match embedded {
+            Some(record) => record?,
+            None if sidecar_path(path).exists() => DesignRecord::from_json(&fs::read_to_string(sidecar_path(path))?)?,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("no design record in {}", path.display()),
+                ))
+            }
+        }
+    };
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{write_iges, write_step, write_stl, StlFormat, StlSolid};
+    use crate::nurbs::fit_wall_surface;
+
+    fn record() -> DesignRecord {
+        let model = AxisymOSWG {
+            k: 1.0,
+            r_init: 25.4,
+            alpha_init: 1.0f64.to_radians(),
+            tilt: 0.0,
+            s: 0.7,
+            q: 0.997,
+            n: 6.0,
+            termination: None,
+            alpha: 45.0f64.to_radians(),
+        };
+        DesignRecord::new(DesignModel::AxisymOSWG(model), 100.0, 12, 10)
+    }
+
+    #[test]
+    fn json_round_trip() {
+        for output in [DesignOutput::Wall, DesignOutput::Solid { thickness: 6.0 }, DesignOutput::Derived("baffle".to_string())] {
+            let record = DesignRecord { output: output.clone(), ..record() };
+            let read = DesignRecord::from_json(&record.to_json()).unwrap();
+            assert_eq!(read.to_json(), record.to_json());
+            assert_eq!(read.output, output);
+        }
+
+        // Records written before the output was recorded are of the wall
+        let legacy = record().to_json().replace(",\"output\":\"Wall\"", "");
+        assert!(!legacy.contains("output"));
+        assert_eq!(DesignRecord::from_json(&legacy).unwrap().output, DesignOutput::Wall);
+    }
+
+    #[test]
+    fn regenerates_the_recorded_output() {
+        let wall = record().generate_mesh().unwrap();
+        assert_eq!(wall.len(), record().generate_wall().unwrap().len());
+        let solid = DesignRecord { output: DesignOutput::Solid { thickness: 6.0 }, ..record() };
+        assert!(solid.generate_mesh().unwrap().len() > wall.len());
+        let derived = DesignRecord { output: DesignOutput::Derived("enclosure".to_string()), ..record() };
+        assert_eq!(derived.generate_mesh().unwrap_err().kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn read_record_round_trip() {
+        // A foreign version, quoted and escaped by the exporters
+        let record = DesignRecord { version: "0.0.9-o'neil\\é".to_string(), ..record() };
+        let directory = std::env::temp_dir();
+        let solids = [StlSolid { name: "wall".to_string(), triangles: record.generate_wall().unwrap() }];
+
+        let ascii = directory.join("compression_waveguide_record_ascii.stl");
+        write_stl(&solids, StlFormat::Ascii, Some(&record), &ascii).unwrap();
+        let binary = directory.join("compression_waveguide_record_binary.stl");
+        write_stl(&solids, StlFormat::Binary, Some(&record), &binary).unwrap();
+        let (surface, _) = fit_wall_surface(0.1, |azimuth, axial| {
+            let record = DesignRecord { azimuth: azimuth.into(), axial: axial.into(), ..record.clone() };
+            Ok(record.generate_profiles()?.iter().map(|profile| profile.iter().map(|point| record.place_point(point)).collect()).collect())
+        })
+        .unwrap();
+        let step = directory.join("compression_waveguide_record.step");
+        write_step(std::slice::from_ref(&surface), false, "wall", Some(&record), &step).unwrap();
+        let iges = directory.join("compression_waveguide_record.igs");
+        write_iges(&[surface], "paroi ancrée", Some(&record), &iges).unwrap();
+        assert!(std::fs::read_to_string(&iges).unwrap().lines().all(|line| line.len() == 80));
+        let json = directory.join("compression_waveguide_record.json");
+        write_record(&record, &json).unwrap();
+
+        for path in [ascii, binary, step, iges, json] {
+            let read = read_record(&path).unwrap();
+            assert_eq!(read.to_json(), record.to_json(), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn step_strings_are_unescaped() {
+        assert_eq!(step_unescape("'tom''s \\\\ horn'"), "'tom's \\ horn'");
+        assert_eq!(step_unescape("Cl\\X2\\00E9\\X0\\ac''h"), "Cléac'h");
+        assert_eq!(step_unescape("\\X2\\D83DDE00\\X0\\"), "\u{1F600}");
+    }
+}
@@ -0,0 +1,243 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::mesh::Symmetry;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefectKind {
+    NotFinite,
+    ZeroArea,
+    Inverted, // normal opposite to the surrounding triangles
+}
+
+/// Bad triangle of a profile grid, located by the grid cell it splits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Defect {
+    pub theta_index: usize,
+    pub z_index: usize,
+    pub kind: DefectKind,
+}
+
+impl fmt::Display for Defect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            DefectKind::NotFinite => "non-finite vertex",
+            DefectKind::ZeroArea => "zero-area triangle",
+            DefectKind::Inverted => "inverted triangle",
+        };
+        write!(f, "{} at θ index {}, z index {}", kind, self.theta_index, self.z_index)
+    }
+}
+
+/// Triangles of grid cell (i, j), split like `triangulate_profiles`
+fn cell_triangles(grid: &[Vec<CartesianPoint>], i: usize, j: usize) -> [[CartesianPoint; 3]; 2] {
+    let next = (i + 1) % grid.len();
+    let (p0, p1, p2, p3) = (grid[i][j], grid[i][j + 1], grid[next][j], grid[next][j + 1]);
+    [[p0, p2, p1], [p1, p2, p3]]
+}
+
+fn normal(triangle: &[CartesianPoint; 3]) -> CartesianPoint {
+    (triangle[1] - triangle[0]).cross(&(triangle[2] - triangle[0]))
+}
+
+/// Finds the degenerate triangles of a placed profile grid (`grid[θ index][z index]`), closed
+/// over the full turn or open over a symmetry sector
+pub fn grid_defects(grid: &[Vec<CartesianPoint>], closed: bool) -> Vec<Defect> {
+    let points = grid.iter().map(Vec::len).min().unwrap_or(0);
+    let cells = if closed { grid.len() } else { grid.len().saturating_sub(1) };
+    if points < 2 || cells == 0 {
+        return Vec::new();
+    }
+
+    // Unit normals of every triangle, zero when not usable as a reference
+    let normals: Vec<Vec<[CartesianPoint; 2]>> = (0..cells)
+        .map(|i| {
+            (0..points - 1)
+                .map(|j| {
+                    cell_triangles(grid, i, j).map(|triangle| {
+                        let n = normal(&triangle);
+                        if n.norm().is_finite() && n.norm() > 0.0 {
+                            n * (1.0 / n.norm())
+                        } else {
+                            CartesianPoint { x: 0.0, y: 0.0, z: 0.0 }
+                        }
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut defects = Vec::new();
+    for i in 0..cells {
+        for j in 0..points - 1 {
+            for (k, triangle) in cell_triangles(grid, i, j).iter().enumerate() {
+                let longest = (0..3)
+                    .map(|e| (triangle[(e + 1) % 3] - triangle[e]).norm())
+                    .fold(0.0, f64::max);
+                let area = normal(triangle).norm();
+
+                let kind = if !triangle.iter().all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite()) {
+                    Some(DefectKind::NotFinite)
+                } else if area <= 1e-12 * longest * longest {
+                    Some(DefectKind::ZeroArea)
+                } else {
+                    // Sum of the neighbouring cells' normals, wrapping in θ on a closed grid
+                    let mut reference = CartesianPoint { x: 0.0, y: 0.0, z: 0.0 };
+                    for di in [-1i64, 0, 1] {
+                        let ni = i as i64 + di;
+                        let ni = if closed { ni.rem_euclid(cells as i64) } else { ni };
+                        for dj in [-1i64, 0, 1] {
+                            let nj = j as i64 + dj;
+                            if ni < 0 || ni >= cells as i64 || nj < 0 || nj >= (points - 1) as i64 {
+                                continue;
+                            }
+                            for (l, n) in normals[ni as usize][nj as usize].iter().enumerate() {
+                                if (ni as usize, nj as usize, l) != (i, j, k) {
+                                    reference = reference + *n;
+                                }
+                            }
+                        }
+                    }
+                    (normal(triangle).dot(&reference) < 0.0).then_some(DefectKind::Inverted)
+                };
+
+                if let Some(kind) = kind {
+                    defects.push(Defect { theta_index: i, z_index: j, kind });
+                }
+            }
+        }
+    }
+
+    defects
+}
+
+/// Replaces the non-finite points of the profiles by the (z, r) of `resample(θ index, z index)`
+/// (typically the model evaluated at a slightly shifted angle) kept at their own angle, or
+/// failing that by the mean of their finite neighbours along θ, then along z. Points of the
+/// zero-area and inverted cells of the grid they place into (see `place_profiles`) are
+/// re-sampled the same way. Returns the defects left afterwards.
+pub fn repair_profiles(
+    profiles: &mut [Vec<ProfilePoint>],
+    symmetry: Symmetry,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+    resample: impl Fn(usize, usize) -> Option<ProfilePoint>,
+) -> Vec<Defect> {
+    let closed = symmetry == Symmetry::Full;
+    let finite = |p: &ProfilePoint| p.z.is_finite() && p.r.is_finite();
+    let resampled = |i: usize, j: usize, theta: f64| {
+        resample(i, j)
+            .filter(finite)
+            .map(|point| ProfilePoint { theta, ..point })
+    };
+
+    for i in 0..profiles.len() {
+        for j in 0..profiles[i].len() {
+            let theta = profiles[i][j].theta;
+            if finite(&profiles[i][j]) {
+                continue;
+            }
+            if let Some(point) = resampled(i, j, theta) {
+                profiles[i][j] = point;
+                continue;
+            }
+
+            let mut theta_neighbours = Vec::new();
+            if i > 0 || closed {
+                theta_neighbours.push((i + profiles.len() - 1) % profiles.len());
+            }
+            if i + 1 < profiles.len() || closed {
+                theta_neighbours.push((i + 1) % profiles.len());
+            }
+            let along_theta: Vec<ProfilePoint> = theta_neighbours
+                .into_iter()
+                .filter_map(|n| profiles[n].get(j).copied())
+                .filter(finite)
+                .collect();
+            let along_z: Vec<ProfilePoint> = [j.wrapping_sub(1), j + 1]
+                .into_iter()
+                .filter_map(|n| profiles[i].get(n).copied())
+                .filter(finite)
+                .collect();
+
+            let neighbours = if along_theta.is_empty() { along_z } else { along_theta };
+            if !neighbours.is_empty() {
+                let count = neighbours.len() as f64;
+                profiles[i][j] = ProfilePoint {
+                    z: neighbours.iter().map(|p| p.z).sum::<f64>() / count,
+                    r: neighbours.iter().map(|p| p.r).sum::<f64>() / count,
+                    theta,
+                };
+            }
+        }
+    }
+
+    for defect in grid_defects(&place_profiles(profiles, symmetry, &place), closed) {
+        let next = (defect.theta_index + 1) % profiles.len();
+        for (i, j) in [(defect.theta_index, defect.z_index), (next, defect.z_index)]
+            .into_iter()
+            .flat_map(|(i, j)| [(i, j), (i, j + 1)])
+        {
+            if let Some(point) = resampled(i, j, profiles[i][j].theta) {
+                profiles[i][j] = point;
+            }
+        }
+    }
+
+    grid_defects(&place_profiles(profiles, symmetry, &place), closed)
+}
+
+/// Places the profiles of the `symmetry` sector as a grid, `grid[θ index][z index]`, the points
+/// of the sector edges snapped onto the mirror planes
+pub fn place_profiles(
+    profiles: &[Vec<ProfilePoint>],
+    symmetry: Symmetry,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+) -> Vec<Vec<CartesianPoint>> {
+    profiles
+        .iter()
+        .map(|profile| profile.iter().map(|point| symmetry.snap(place(point), point.theta)).collect())
+        .collect()
+}
+
+/// Profiles of the `symmetry` sector with the defects of their placed grid (see
+/// `place_profiles`). With `repair`, they go through `repair_profiles`, re-sampling `profile`
+/// (the profile at an angle, if it can be built) at a slightly shifted angle, and the defects
+/// are those left afterwards.
+pub fn check_profiles(
+    mut profiles: Vec<Vec<ProfilePoint>>,
+    symmetry: Symmetry,
+    repair: bool,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+    profile: impl Fn(f64) -> Option<Vec<ProfilePoint>>,
+) -> (Vec<Vec<ProfilePoint>>, Vec<Defect>) {
+    let defects = if repair {
+        let thetas: Vec<f64> = profiles.iter().map(|profile| profile[0].theta).collect();
+        repair_profiles(&mut profiles, symmetry, &place, |i, j| profile(thetas[i] + 1e-6)?.get(j).copied())
+    } else {
+        grid_defects(&place_profiles(&profiles, symmetry, &place), symmetry == Symmetry::Full)
+    };
+    (profiles, defects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn repaired_edge_points_stay_on_the_mirror_plane() {
+        let cone = |theta: f64| -> Vec<ProfilePoint> {
+            (0..5).map(|j| ProfilePoint { z: 10.0 * j as f64, r: 10.0 + 5.0 * j as f64, theta }).collect()
+        };
+        let mut profiles: Vec<Vec<ProfilePoint>> = (0..=4).map(|i| cone(PI * i as f64 / 4.0)).collect();
+        profiles[4][2].r = f64::NAN;
+        let place = |point: &ProfilePoint| CartesianPoint::from_cylindrical(point.r, point.theta, point.z);
+
+        let thetas: Vec<f64> = profiles.iter().map(|profile| profile[0].theta).collect();
+        let defects = repair_profiles(&mut profiles, Symmetry::Half, place, |i, j| cone(thetas[i] + 1e-6).get(j).copied());
+        assert!(defects.is_empty());
+        assert_eq!(profiles[4][2].theta, PI);
+        let grid = place_profiles(&profiles, Symmetry::Half, place);
+        assert_eq!(grid[4][2].y, 0.0);
+        assert!((grid[4][2].x + 20.0).abs() < 1e-9);
+    }
+}
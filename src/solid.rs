@@ -0,0 +1,206 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::mesh::triangulate_profiles;
+
+/// Builds a closed shell around the waveguide wall: the inner wall (the profiles), an outer wall
+/// offset by `thickness` along the profile normals, and the rims joining them at the throat and mouth.
+///
+/// Each angle is handled as one closed cross-section loop (inner wall, mouth rim, outer wall back
+/// to the throat, throat rim), so profiles that roll back past 90° and overlap in z are fine.
+/// Where the mouth end already lies on the outer wall (a `Termination::Lip`), the mouth rim
+/// of that angle collapses and the lip closes onto the outer wall.
+/// `profiles` must all have the same number of points; `place` maps them to 3D.
+pub fn generate_solid(
+    profiles: &[Vec<ProfilePoint>],
+    thickness: f64,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+) -> Vec<[CartesianPoint; 3]> {
+    let loops: Vec<Vec<CartesianPoint>> = profiles
+        .iter()
+        .zip(outer_walls(profiles, thickness).0)
+        .map(|(profile, outer)| {
+            // Walked backwards so the normals point out of the material
+            profile
+                .iter()
+                .copied()
+                .chain(outer)
+                .chain(std::iter::once(profile[0]))
+                .rev()
+                .map(|point| place(&point))
+                .collect()
+        })
+        .collect();
+
+    // The collapsed rims leave triangles with two coincident corners
+    triangulate_profiles(&loops)
+        .into_iter()
+        .filter(|triangle| (0..3).all(|k| (triangle[(k + 1) % 3] - triangle[k]).norm() > 0.0))
+        .collect()
+}
+
+/// Faces of the same shell as families of profiles, each face starting where the previous one
+/// ends: inner wall, mouth rim (absent when the lip closes at every angle), outer wall and
+/// throat rim
+pub fn shell_faces(profiles: &[Vec<ProfilePoint>], thickness: f64) -> Vec<Vec<Vec<ProfilePoint>>> {
+    let (outer_walls, closed_lips) = outer_walls(profiles, thickness);
+    let mouth = |profile: &Vec<ProfilePoint>| profile[profile.len() - 1];
+
+    let mut faces = vec![profiles.to_vec()];
+    if !closed_lips.iter().all(|&closed| closed) {
+        faces.push(profiles.iter().zip(&outer_walls).map(|(profile, outer)| vec![mouth(profile), outer[0]]).collect());
+    }
+    faces.push(outer_walls.clone());
+    faces.push(
+        profiles
+            .iter()
+            .zip(&outer_walls)
+            .map(|(profile, outer)| vec![outer[outer.len() - 1], profile[0]])
+            .collect(),
+    );
+    faces
+}
+
+/// Outer walls of every profile, and whether the mouth end of each already lies on its outer
+/// wall, which then starts exactly there so the mouth rim collapses
+fn outer_walls(profiles: &[Vec<ProfilePoint>], thickness: f64) -> (Vec<Vec<ProfilePoint>>, Vec<bool>) {
+    profiles
+        .iter()
+        .map(|profile| {
+            let (outer, mouth_gap) = outer_wall(profile, thickness);
+            let closed_lip = mouth_gap < 0.01 * thickness;
+            let theta = profile[0].theta;
+            let mut outer: Vec<ProfilePoint> = outer.into_iter().map(|(z, r)| ProfilePoint { z, r, theta }).collect();
+            if closed_lip {
+                outer[0] = profile[profile.len() - 1];
+            }
+            (outer, closed_lip)
+        })
+        .unzip()
+}
+
+/// Outer wall of one profile as (z, r) points from the mouth back to the throat, resampled to
+/// the profile's point count, and the distance between the mouth end and the outer wall
+fn outer_wall(profile: &[ProfilePoint], thickness: f64) -> (Vec<(f64, f64)>, f64) {
+    let n = profile.len();
+
+    // Offset along the normal pointing away from the axis side of the wall
+    let offset: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let (dz, dr) = tangent(profile, i);
+            (profile[i].z - thickness * dr, profile[i].r + thickness * dz)
+        })
+        .collect();
+
+    // The outer wall ends at the point closest to the mouth end; the first closest one is
+    // on the unrolled part of the wall, the offset of a lip lies inside the lip
+    let mouth = (profile[n - 1].z, profile[n - 1].r);
+    let mut closest = (f64::INFINITY, 0, offset[0]);
+    for i in 0..n - 1 {
+        let (a, b) = (offset[i], offset[i + 1]);
+        let (dz, dr) = (b.0 - a.0, b.1 - a.1);
+        let fraction = (((mouth.0 - a.0) * dz + (mouth.1 - a.1) * dr) / (dz * dz + dr * dr))
+            .clamp(0.0, 1.0);
+        let point = (a.0 + fraction * dz, a.1 + fraction * dr);
+        let distance = (point.0 - mouth.0).hypot(point.1 - mouth.1);
+        if distance < closest.0 - 1e-9 {
+            closest = (distance, i, point);
+        }
+    }
+    let (mouth_gap, segment, end) = closest;
+
+    let path: Vec<(f64, f64)> = std::iter::once(end)
+        .chain(offset[..=segment].iter().rev().copied())
+        .collect();
+
+    (resample(&path, n), mouth_gap)
+}
+
+/// Unit tangent of the profile at point `i`, from its neighbours, widened past repeated points;
+/// along the axis when all the points coincide
+fn tangent(profile: &[ProfilePoint], i: usize) -> (f64, f64) {
+    let last = profile.len() - 1;
+    let (mut prev, mut next) = (i, i);
+    while prev > 0 || next < last {
+        (prev, next) = (prev.saturating_sub(1), (next + 1).min(last));
+        let (dz, dr) = (profile[next].z - profile[prev].z, profile[next].r - profile[prev].r);
+        let norm = dz.hypot(dr);
+        if norm > 0.0 {
+            return (dz / norm, dr / norm);
+        }
+    }
+    (1.0, 0.0)
+}
+
+/// `count` points evenly spaced along a polyline, keeping both ends
+fn resample(path: &[(f64, f64)], count: usize) -> Vec<(f64, f64)> {
+    let mut cumulative = vec![0.0];
+    for pair in path.windows(2) {
+        let step = (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1);
+        cumulative.push(cumulative[cumulative.len() - 1] + step);
+    }
+    let total = cumulative[cumulative.len() - 1];
+
+    (0..count)
+        .map(|i| {
+            let target = total * (i as f64) / ((count - 1) as f64);
+            let segment = cumulative
+                .partition_point(|&c| c <= target)
+                .clamp(1, path.len() - 1);
+            let (a, b) = (path[segment - 1], path[segment]);
+            let length = cumulative[segment] - cumulative[segment - 1];
+            let fraction = if length > 0.0 {
+                (target - cumulative[segment - 1]) / length
+            } else {
+                0.0
+            };
+            (a.0 + fraction * (b.0 - a.0), a.1 + fraction * (b.1 - a.1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{Mesh, SurfaceTag};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn repeated_points_keep_the_outer_wall_finite() {
+        // A termination of zero length repeats the mouth point
+        let profiles: Vec<Vec<ProfilePoint>> = (0..8)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 8.0;
+                let mut profile: Vec<ProfilePoint> =
+                    (0..5).map(|j| ProfilePoint { z: 10.0 * j as f64, r: 10.0 + 5.0 * j as f64, theta }).collect();
+                profile.extend([profile[4]; 3]);
+                profile
+            })
+            .collect();
+        let place = |point: &ProfilePoint| CartesianPoint::from_cylindrical(point.r, point.theta, point.z);
+        for triangle in generate_solid(&profiles, 2.0, place) {
+            assert!(triangle.iter().all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite()));
+        }
+    }
+
+    #[test]
+    fn lips_close_at_their_own_angles() {
+        // Cylinder walls, every other one hooking back onto its outer wall 2 away
+        let profiles: Vec<Vec<ProfilePoint>> = (0..8)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 8.0;
+                let points: Vec<(f64, f64)> = if i % 2 == 0 {
+                    (0..6).map(|j| (10.0 * j as f64, 10.0)).chain([(52.0, 11.0), (50.0, 12.0), (48.0, 12.0)]).collect()
+                } else {
+                    (0..9).map(|j| (10.0 * j as f64, 10.0)).collect()
+                };
+                points.into_iter().map(|(z, r)| ProfilePoint { z, r, theta }).collect()
+            })
+            .collect();
+        let (_, closed) = outer_walls(&profiles, 2.0);
+        assert_eq!(closed, (0..8).map(|i| i % 2 == 0).collect::<Vec<bool>>());
+
+        let place = |point: &ProfilePoint| CartesianPoint::from_cylindrical(point.r, point.theta, point.z);
+        let mesh = Mesh::from_triangles(&generate_solid(&profiles, 2.0, place), SurfaceTag::Wall);
+        assert!(mesh.boundary_loops().is_empty());
+        assert_eq!(shell_faces(&profiles, 2.0).len(), 4);
+    }
+}
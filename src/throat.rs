@@ -0,0 +1,278 @@
+use crate::geometry_types::{CartesianPoint, ProfilePoint};
+use crate::mesh::{Mesh, SurfaceTag};
+use crate::solid::generate_solid;
+use crate::trim::{mouth_centre, require_mouth_loop, Plane};
+use std::io;
+
+/// Section added in front of the throat to match the driver exit, on surface meshes only (see
+/// `add_throat_extension`): solids keep the bare throat, printed parts couple to the driver
+/// with a `ThroatAdapter`
+#[derive(Debug, Clone, Copy)]
+pub enum ThroatExtension {
+    Cylindrical { length: f64 },
+    Conical { length: f64, angle: f64 }, // half-angle, positive narrows towards the driver
+}
+
+/// Surface closing the throat
+#[derive(Debug, Clone, Copy)]
+pub enum ThroatCap {
+    Flat,
+    Spherical { radius: f64 }, // wavefront radius, the cap bulges into the waveguide
+}
+
+impl ThroatCap {
+    /// Spherical wavefront normal to a wall leaving the throat radius `r_init` at `alpha_init`
+    /// from the axis, centred on the apex of that cone; flat for a parallel wall
+    pub fn wavefront(r_init: f64, alpha_init: f64) -> Self {
+        if alpha_init.abs() < 1e-9 {
+            ThroatCap::Flat
+        } else {
+            ThroatCap::Spherical {
+                radius: r_init / alpha_init.sin(),
+            }
+        }
+    }
+}
+
+/// Printable tube joining a round driver exit, `length` behind the throat, to the throat
+#[derive(Debug, Clone, Copy)]
+pub struct ThroatAdapter {
+    pub length: f64,
+    pub exit_radius: f64,
+    pub thickness: f64, // wall
+}
+
+/// Plate bolted between the driver and the driver end of a `ThroatAdapter`
+#[derive(Debug, Clone, Copy)]
+pub struct Flange {
+    pub outer_radius: f64,
+    pub thickness: f64,
+}
+
+/// Closed solid of the adapter, its bore straight from the exit circle to the throat contour
+/// given by the first point of every profile. `place` maps profile points to 3D.
+pub fn generate_throat_adapter(
+    profiles: &[Vec<ProfilePoint>],
+    adapter: &ThroatAdapter,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+) -> Vec<[CartesianPoint; 3]> {
+    let bores: Vec<Vec<ProfilePoint>> = profiles
+        .iter()
+        .map(|profile| {
+            let throat = profile[0];
+            vec![
+                ProfilePoint { z: throat.z - adapter.length, r: adapter.exit_radius, theta: throat.theta },
+                throat,
+            ]
+        })
+        .collect();
+    generate_solid(&bores, adapter.thickness, place)
+}
+
+/// Closed solid of the flange: an annulus from the adapter exit out to `outer_radius`, against
+/// the driver end of the adapter, at the angles of `profiles`
+pub fn generate_flange(
+    profiles: &[Vec<ProfilePoint>],
+    adapter: &ThroatAdapter,
+    flange: &Flange,
+    place: impl Fn(&ProfilePoint) -> CartesianPoint,
+) -> Vec<[CartesianPoint; 3]> {
+    // The outer wall of a flaring adapter is offset past its driver end
+    let overhang = profiles
+        .iter()
+        .map(|profile| {
+            let flare = (profile[0].r - adapter.exit_radius).max(0.0);
+            adapter.thickness * flare / adapter.length.hypot(flare)
+        })
+        .fold(0.0, f64::max);
+
+    let faces: Vec<Vec<ProfilePoint>> = profiles
+        .iter()
+        .map(|profile| {
+            let (z, theta) = (profile[0].z - adapter.length - overhang, profile[0].theta);
+            vec![
+                ProfilePoint { z, r: adapter.exit_radius, theta },
+                ProfilePoint { z, r: flange.outer_radius, theta },
+            ]
+        })
+        .collect();
+    // A radial profile is offset towards -z, away from the adapter
+    generate_solid(&faces, flange.thickness, place)
+}
+
+/// Throat edge lying in `plane` with its centre and mean edge length
+fn throat_edge(mesh: &Mesh, plane: &Plane) -> io::Result<(Vec<usize>, CartesianPoint, f64)> {
+    let throat = require_mouth_loop(mesh, plane)?;
+    let centre = mouth_centre(mesh, plane, &throat);
+    let segment = (0..throat.len())
+        .map(|k| (mesh.vertices[throat[(k + 1) % throat.len()]] - mesh.vertices[throat[k]]).norm())
+        .sum::<f64>()
+        / throat.len() as f64;
+    Ok((throat, centre, segment))
+}
+
+/// Extends the wall from the throat edge lying in `plane` (normal pointing into the waveguide)
+/// back towards the driver. Returns the plane of the new throat edge. Fails when no throat
+/// edge lies in `plane`, or when a conical extension narrows past the axis.
+pub fn add_throat_extension(mesh: &mut Mesh, plane: &Plane, extension: &ThroatExtension) -> io::Result<Plane> {
+    let (throat, centre, segment) = throat_edge(mesh, plane)?;
+    let (length, taper) = match *extension {
+        ThroatExtension::Cylindrical { length } => (length, 0.0),
+        ThroatExtension::Conical { length, angle } => (length, angle.tan()),
+    };
+    let narrowest = throat.iter().map(|&i| (mesh.vertices[i] - centre).norm()).fold(f64::INFINITY, f64::min);
+    if length.is_nan() || length <= 0.0 || length * taper >= narrowest {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} does not fit a throat {} in radius at its narrowest", extension, narrowest),
+        ));
+    }
+
+    let steps = (length / segment).ceil().max(1.0) as usize;
+    let mut inner = throat.clone();
+    for k in 1..=steps {
+        let depth = length * k as f64 / steps as f64;
+        let outer: Vec<usize> = throat
+            .iter()
+            .map(|&i| {
+                let radial = mesh.vertices[i] - centre;
+                let scale = 1.0 - depth * taper / radial.norm();
+                mesh.add_vertex(centre + radial * scale - plane.normal * depth)
+            })
+            .collect();
+        mesh.add_strip(&inner, &outer, SurfaceTag::Wall);
+        inner = outer;
+    }
+
+    Ok(Plane {
+        point: plane.point - plane.normal * length,
+        normal: plane.normal,
+    })
+}
+
+/// Wall triangles (as from `generate_mesh`) with the throat in the plane z = 0 closed by `cap`,
+/// tagged as the source. Fails like `add_throat_cap`.
+pub fn generate_source_mesh(wall: &[[CartesianPoint; 3]], cap: &ThroatCap) -> io::Result<Mesh> {
+    let mut mesh = Mesh::from_triangles(wall, SurfaceTag::Wall);
+    add_throat_cap(&mut mesh, &Plane::at_z(0.0), cap)?;
+    Ok(mesh)
+}
+
+/// Closes the throat edge lying in `plane` (normal pointing into the waveguide) with concentric
+/// rings, tagged `Source`. Fails when no throat edge lies in `plane`.
+pub fn add_throat_cap(mesh: &mut Mesh, plane: &Plane, cap: &ThroatCap) -> io::Result<()> {
+    let (throat, centre, segment) = throat_edge(mesh, plane)?;
+    let radius = match *cap {
+        ThroatCap::Flat => f64::INFINITY,
+        ThroatCap::Spherical { radius } => radius,
+    };
+    // Height of the sphere above the plane at a distance from the centre, zero on the edge
+    let sagitta = |distance: f64, edge: f64| {
+        if radius.is_finite() {
+            let radius = radius.max(edge);
+            (radius * radius - distance * distance).sqrt() - (radius * radius - edge * edge).sqrt()
+        } else {
+            0.0
+        }
+    };
+
+    let mean_radius = throat
+        .iter()
+        .map(|&i| (mesh.vertices[i] - centre).norm())
+        .sum::<f64>()
+        / throat.len() as f64;
+    let steps = (mean_radius / segment).ceil().max(1.0) as usize;
+    let mut inner = throat.clone();
+    for k in 1..steps {
+        let scale = 1.0 - k as f64 / steps as f64;
+        let outer: Vec<usize> = throat
+            .iter()
+            .map(|&i| {
+                let radial = mesh.vertices[i] - centre;
+                let edge = radial.norm();
+                let height = sagitta(edge * scale, edge);
+                mesh.add_vertex(centre + radial * scale + plane.normal * height)
+            })
+            .collect();
+        mesh.add_strip(&inner, &outer, SurfaceTag::Source);
+        inner = outer;
+    }
+    let apex = mesh.add_vertex(centre + plane.normal * sagitta(0.0, mean_radius));
+    mesh.add_strip(&inner, &vec![apex; inner.len()], SurfaceTag::Source);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// Cone from r = 10 at z = 0 to r = 60 at z = 100, 16 angles
+    fn cone() -> Mesh {
+        let grid: Vec<Vec<CartesianPoint>> = (0..16)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 16.0;
+                (0..6).map(|j| CartesianPoint::from_cylindrical(10.0 + 10.0 * j as f64, theta, 20.0 * j as f64)).collect()
+            })
+            .collect();
+        Mesh::from_grid(&grid, true, SurfaceTag::Wall)
+    }
+
+    #[test]
+    fn extension_moves_the_throat_back() {
+        let throat = Plane::at_z(0.0);
+        let conical = ThroatExtension::Conical { length: 20.0, angle: 0.3 };
+        let mut mesh = cone();
+        let plane = add_throat_extension(&mut mesh, &throat, &conical).unwrap();
+        assert!((plane.point.z + 20.0).abs() < 1e-12);
+        let edge = require_mouth_loop(&mesh, &plane).unwrap();
+        assert_eq!(edge.len(), 16);
+        for &i in &edge {
+            let vertex = mesh.vertices[i];
+            assert!((vertex.x.hypot(vertex.y) - (10.0 - 20.0 * 0.3f64.tan())).abs() < 1e-9);
+        }
+        assert_eq!(mesh.boundary_loops().len(), 2);
+
+        for extension in [
+            ThroatExtension::Cylindrical { length: 0.0 },
+            ThroatExtension::Conical { length: 40.0, angle: 0.3 },
+        ] {
+            assert!(add_throat_extension(&mut cone(), &throat, &extension).is_err());
+        }
+        let cylindrical = ThroatExtension::Cylindrical { length: 20.0 };
+        assert!(add_throat_extension(&mut cone(), &Plane::at_z(-5.0), &cylindrical).is_err());
+    }
+
+    #[test]
+    fn cap_closes_the_throat() {
+        let area = |mesh: &Mesh| -> f64 {
+            mesh.triangles
+                .iter()
+                .zip(&mesh.tags)
+                .filter(|(_, &tag)| tag == SurfaceTag::Source)
+                .map(|(face, _)| {
+                    let [a, b, c] = face.map(|i| mesh.vertices[i]);
+                    (b - a).cross(&(c - a)).norm() / 2.0
+                })
+                .sum()
+        };
+        let polygon = 8.0 * 100.0 * (2.0 * PI / 16.0).sin();
+
+        let mut flat = cone();
+        add_throat_cap(&mut flat, &Plane::at_z(0.0), &ThroatCap::Flat).unwrap();
+        assert!((area(&flat) - polygon).abs() < 1e-9);
+        assert_eq!(flat.boundary_loops().len(), 1);
+
+        // Bulging into the waveguide, short of the smooth spherical cap
+        let radius: f64 = 20.0;
+        let height = radius - (radius * radius - 100.0).sqrt();
+        let mut spherical = cone();
+        add_throat_cap(&mut spherical, &Plane::at_z(0.0), &ThroatCap::Spherical { radius }).unwrap();
+        assert!(area(&spherical) > polygon && area(&spherical) < 2.0 * PI * radius * height);
+        let apex = spherical.vertices[spherical.vertices.len() - 1].z;
+        assert!((apex - height).abs() < 1e-9);
+        assert_eq!(spherical.boundary_loops().len(), 1);
+
+        assert!(add_throat_cap(&mut cone(), &Plane::at_z(10.0), &ThroatCap::Flat).is_err());
+    }
+}
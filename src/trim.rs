@@ -0,0 +1,231 @@
+use crate::geometry_types::CartesianPoint;
+use crate::mesh::{Mesh, SurfaceTag};
+use std::collections::HashMap;
+use std::io;
+
+/// Plane through `point` with unit `normal`; the normal points to the side that gets cut away
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    pub point: CartesianPoint,
+    pub normal: CartesianPoint,
+}
+
+impl Plane {
+    /// Plane z = `z`, keeping everything below it
+    pub fn at_z(z: f64) -> Self {
+        Self {
+            point: CartesianPoint { x: 0.0, y: 0.0, z },
+            normal: CartesianPoint { x: 0.0, y: 0.0, z: 1.0 },
+        }
+    }
+
+    /// Plane through the axis at `z`, tilted by `tilt` about the x axis like the waveguide tilt
+    pub fn tilted(z: f64, tilt: f64) -> Self {
+        Self {
+            point: CartesianPoint { x: 0.0, y: 0.0, z },
+            normal: CartesianPoint { x: 0.0, y: -tilt.sin(), z: tilt.cos() },
+        }
+    }
+
+    /// Mouth plane z = `length` of a waveguide, following its tilted axis
+    pub fn waveguide_mouth(length: f64, tilt: f64) -> Self {
+        Self {
+            point: CartesianPoint { x: 0.0, y: 0.0, z: length }.tilted(tilt, length),
+            normal: CartesianPoint { x: 0.0, y: -tilt.sin(), z: tilt.cos() },
+        }
+    }
+
+    /// Orthonormal in-plane axes (u, v), u following x as closely as possible
+    pub fn axes(&self) -> (CartesianPoint, CartesianPoint) {
+        let x = CartesianPoint { x: 1.0, y: 0.0, z: 0.0 };
+        let u = x - self.normal * x.dot(&self.normal);
+        let u = if u.norm() > 1e-9 {
+            u * (1.0 / u.norm())
+        } else {
+            CartesianPoint { x: 0.0, y: 1.0, z: 0.0 }
+        };
+        (u, self.normal.cross(&u))
+    }
+
+    /// Signed distance, positive on the cut side
+    pub fn distance(&self, point: &CartesianPoint) -> f64 {
+        (*point - self.point).dot(&self.normal)
+    }
+
+    pub fn project(&self, point: &CartesianPoint) -> CartesianPoint {
+        *point - self.normal * self.distance(point)
+    }
+}
+
+/// Cuts the mesh at `plane`, keeping the part on the throat side. Triangles crossing the plane
+/// are clipped and the new edge vertices are projected onto the plane, so the mouth edge is
+/// exactly planar.
+pub fn trim_at_plane(mesh: &Mesh, plane: &Plane) -> Mesh {
+    let tolerance = 1e-9;
+    let distances: Vec<f64> = mesh.vertices.iter().map(|v| plane.distance(v)).collect();
+
+    let mut trimmed = Mesh {
+        symmetry: mesh.symmetry,
+        ..Mesh::default()
+    };
+    let mut kept: HashMap<usize, usize> = HashMap::new();
+    let mut crossings: HashMap<(usize, usize), usize> = HashMap::new();
+
+    for (face, &tag) in mesh.triangles.iter().zip(&mesh.tags) {
+        if face.iter().all(|&i| distances[i] >= -tolerance) {
+            continue;
+        }
+
+        // Clip the triangle polygon against the plane (Sutherland-Hodgman, single plane)
+        let mut polygon = Vec::with_capacity(4);
+        for k in 0..3 {
+            let (a, b) = (face[k], face[(k + 1) % 3]);
+            let (da, db) = (distances[a], distances[b]);
+            if da <= tolerance {
+                let index = *kept.entry(a).or_insert_with(|| {
+                    let vertex = if da.abs() <= tolerance {
+                        plane.project(&mesh.vertices[a])
+                    } else {
+                        mesh.vertices[a]
+                    };
+                    trimmed.add_vertex(vertex)
+                });
+                polygon.push(index);
+            }
+            if (da < -tolerance && db > tolerance) || (da > tolerance && db < -tolerance) {
+                let key = (a.min(b), a.max(b));
+                let index = *crossings.entry(key).or_insert_with(|| {
+                    let (p, q) = (mesh.vertices[key.0], mesh.vertices[key.1]);
+                    let (dp, dq) = (distances[key.0], distances[key.1]);
+                    let crossing = p + (q - p) * (dp / (dp - dq));
+                    trimmed.add_vertex(plane.project(&crossing))
+                });
+                polygon.push(index);
+            }
+        }
+
+        for k in 1..polygon.len().saturating_sub(1) {
+            trimmed.add_triangle([polygon[0], polygon[k], polygon[k + 1]], tag);
+        }
+    }
+
+    trimmed
+}
+
+/// Boundary loop of the mesh lying in `plane` (the mouth edge), oriented like the wall triangles
+pub fn mouth_loop(mesh: &Mesh, plane: &Plane) -> Option<Vec<usize>> {
+    let tolerance = 1e-6;
+    mesh.boundary_loops()
+        .into_iter()
+        .filter(|boundary| {
+            boundary
+                .iter()
+                .all(|&i| plane.distance(&mesh.vertices[i]).abs() < tolerance)
+        })
+        .max_by_key(|boundary| boundary.len())
+}
+
+/// Centroid of the mouth edge, projected onto `plane`
+pub fn mouth_centre(mesh: &Mesh, plane: &Plane, mouth: &[usize]) -> CartesianPoint {
+    let centre = mouth
+        .iter()
+        .fold(CartesianPoint { x: 0.0, y: 0.0, z: 0.0 }, |sum, &i| sum + mesh.vertices[i])
+        * (1.0 / mouth.len() as f64);
+    plane.project(&centre)
+}
+
+/// Mouth edge lying in `plane`, failing when the mesh has no boundary loop there
+pub fn require_mouth_loop(mesh: &Mesh, plane: &Plane) -> io::Result<Vec<usize>> {
+    mouth_loop(mesh, plane).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no boundary loop of the mesh lies in the plane through {:?}", plane.point),
+        )
+    })
+}
+
+/// Adds a flat ring in `plane` from the mouth edge out to a circle of `outer_radius` around
+/// the mouth centre, so the mesh represents the horn mounted in a baffle.
+/// The mouth edge must be star-shaped around its centroid, which holds for waveguide mouths.
+/// Fails when no mouth edge lies in `plane`, or when the circle does not clear it.
+pub fn add_baffle_ring(mesh: &mut Mesh, plane: &Plane, outer_radius: f64) -> io::Result<()> {
+    let mouth = require_mouth_loop(mesh, plane)?;
+    let centre = mouth_centre(mesh, plane, &mouth);
+    let extent = mouth.iter().map(|&i| (mesh.vertices[i] - centre).norm()).fold(0.0, f64::max);
+    if outer_radius.is_nan() || outer_radius <= extent {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("baffle ring radius {} does not clear the mouth, reaching {} from its centre", outer_radius, extent),
+        ));
+    }
+
+    let outer: Vec<usize> = mouth
+        .iter()
+        .map(|&i| {
+            let direction = mesh.vertices[i] - centre;
+            mesh.add_vertex(centre + direction * (outer_radius / direction.norm()))
+        })
+        .collect();
+
+    mesh.add_strip(&mouth, &outer, SurfaceTag::Baffle);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{TabulatedWG, Waveguide};
+    use std::f64::consts::PI;
+
+    /// Cone from r = 10 at z = 0 to r = 60 at z = 100, 16 angles
+    fn cone() -> Mesh {
+        let grid: Vec<Vec<CartesianPoint>> = (0..16)
+            .map(|i| {
+                let theta = 2.0 * PI * i as f64 / 16.0;
+                (0..6).map(|j| CartesianPoint::from_cylindrical(10.0 + 10.0 * j as f64, theta, 20.0 * j as f64)).collect()
+            })
+            .collect();
+        Mesh::from_grid(&grid, true, SurfaceTag::Wall)
+    }
+
+    #[test]
+    fn trimmed_mouth_lies_in_the_plane() {
+        for plane in [Plane::at_z(70.0), Plane::tilted(70.0, 0.2)] {
+            let trimmed = trim_at_plane(&cone(), &plane);
+            assert!(trimmed.vertices.iter().all(|vertex| plane.distance(vertex) <= 1e-9));
+            assert_eq!(trimmed.boundary_loops().len(), 2);
+            let mouth = require_mouth_loop(&trimmed, &plane).unwrap();
+            assert!(mouth.len() >= 16);
+            assert!(mouth.iter().all(|&i| plane.distance(&trimmed.vertices[i]).abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn baffle_ring_clears_the_mouth() {
+        let plane = Plane::at_z(100.0);
+        let mut mesh = cone();
+        assert!(add_baffle_ring(&mut mesh.clone(), &Plane::at_z(50.0), 200.0).is_err());
+        assert!(add_baffle_ring(&mut mesh.clone(), &plane, 55.0).is_err());
+
+        add_baffle_ring(&mut mesh, &plane, 200.0).unwrap();
+        assert_eq!(mesh.tags.iter().filter(|&&tag| tag == SurfaceTag::Baffle).count(), 32);
+        // The mouth edge is now inside; the throat and the ring rim are left open
+        let loops = mesh.boundary_loops();
+        assert_eq!(loops.len(), 2);
+        let rim = mouth_loop(&mesh, &plane).unwrap();
+        assert!(rim.iter().all(|&i| (mesh.vertices[i].x.hypot(mesh.vertices[i].y) - 200.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn tilted_waveguide_mouth_lies_in_its_plane() {
+        let cone = vec![(0.0, 10.0), (50.0, 35.0), (100.0, 60.0)];
+        let mut waveguide = TabulatedWG::from_profiles(vec![(0.0, cone)]).unwrap();
+        waveguide.tilt = 0.2;
+        let mut mesh = Mesh::from_triangles(&waveguide.generate_mesh(100.0, 16, 11).unwrap(), SurfaceTag::Wall);
+        let plane = Plane::waveguide_mouth(100.0, 0.2);
+        assert_eq!(require_mouth_loop(&mesh, &plane).unwrap().len(), 16);
+        // Unlike the plane z = 100 the untilted mouth would lie in
+        assert!(mouth_loop(&mesh, &Plane::at_z(100.0)).is_none());
+        add_baffle_ring(&mut mesh, &plane, 200.0).unwrap();
+    }
+}